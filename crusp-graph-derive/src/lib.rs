@@ -3,8 +3,8 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, DeriveInput};
 
 macro_rules! span {
     () => {{
@@ -15,514 +15,1178 @@ macro_rules! span {
 #[derive(Debug, Clone)]
 struct GraphElt {
     ident: syn::Ident,
-    node: syn::Ident,
-    event: syn::Ident,
+    node: syn::Type,
+    event: syn::Type,
+    // only set on outputs
+    options: OutputOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+struct GraphOptions {
+    scheduler: Option<syn::Type>,
+}
+
+impl syn::parse::Parse for GraphOptions {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut options = GraphOptions::default();
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "scheduler" {
+                options.scheduler = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(key.span(), format!("unknown option `{}`", key)));
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(options)
+    }
+}
+
+// Options of an output: `#[output(rank = 1, scheduler = FifoScheduler<Node>)]`.
+#[derive(Debug, Clone, Default)]
+struct OutputOptions {
+    // outputs with a lower rank are popped first by the combined pop
+    rank: i64,
+    // overrides the scheduler of the graph
+    scheduler: Option<syn::Type>,
+}
+
+impl syn::parse::Parse for OutputOptions {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut options = OutputOptions::default();
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "rank" {
+                options.rank = input.parse::<syn::LitInt>()?.base10_parse()?;
+            } else if key == "scheduler" {
+                options.scheduler = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(key.span(), format!("unknown output option `{}`", key)));
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(options)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct GraphStructure {
     ident: syn::Ident,
-    out: GraphElt,
+    outs: Vec<GraphElt>,
     ins: Vec<GraphElt>,
 }
 
-fn read_typepath_ident(path: &syn::TypePath) -> syn::Ident {
-    path.path.segments.first().expect("One type").ident.clone()
+// Any type path is accepted, including generic and associated types.
+fn read_type(tp: &syn::Type) -> syn::Result<syn::Type> {
+    match tp {
+        syn::Type::Path(_) => Ok(tp.clone()),
+        // types forwarded by `macro_rules!` are wrapped in invisible groups
+        syn::Type::Group(ref group) => read_type(&group.elem),
+        _ => Err(syn::Error::new_spanned(tp, "expected a node or event type")),
+    }
 }
 
-fn pair_to_idents(pair: &syn::TypeTuple) -> (syn::Ident, syn::Ident) {
-    let pair: Vec<_> = pair
-        .elems
-        .iter()
-        .map(|tp| {
-            if let syn::Type::Path(ref tp) = tp {
-                tp
-            } else {
-                unimplemented!()
-            }
-        })
-        .collect();
-    if pair.len() != 2 {
-        panic!()
+fn pair_to_types(pair: &syn::TypeTuple) -> syn::Result<(syn::Type, syn::Type)> {
+    if pair.elems.len() != 2 {
+        return Err(syn::Error::new_spanned(pair, "expected a `(Node, Event)` pair"));
     }
-    (read_typepath_ident(pair[0]), read_typepath_ident(pair[1]))
+    Ok((read_type(&pair.elems[0])?, read_type(&pair.elems[1])?))
 }
 
-fn field_to_graph_elt(field: &syn::Field) -> GraphElt {
+fn field_to_graph_elt(field: &syn::Field, options: OutputOptions) -> syn::Result<GraphElt> {
     let ident = field.ident.clone().expect("Identifier expected");
     let tuple = if let syn::Type::Tuple(ref tuple) = field.ty {
         tuple
     } else {
-        unimplemented!()
+        return Err(syn::Error::new_spanned(&field.ty, "expected a `(Node, Event)` pair"));
     };
-    let (node, event) = pair_to_idents(tuple);
-    GraphElt { ident, node, event }
+    let (node, event) = pair_to_types(tuple)?;
+    Ok(GraphElt {
+        ident,
+        node,
+        event,
+        options,
+    })
+}
+
+#[derive(Debug, Clone)]
+enum FieldKind {
+    Output(Box<OutputOptions>),
+    Input,
+}
+
+// Every field is annotated by exactly one `#[output]` or `#[input]` attribute.
+fn field_kind(field: &syn::Field) -> syn::Result<FieldKind> {
+    let mut kind = None;
+    for attr in field.attrs.iter() {
+        let attr_kind = if attr.path.is_ident("output") {
+            if attr.tokens.is_empty() {
+                FieldKind::Output(Box::default())
+            } else {
+                FieldKind::Output(Box::new(attr.parse_args()?))
+            }
+        } else if attr.path.is_ident("input") {
+            if !attr.tokens.is_empty() {
+                return Err(syn::Error::new_spanned(&attr.tokens, "unexpected arguments"));
+            }
+            FieldKind::Input
+        } else {
+            continue;
+        };
+        if kind.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "a field is either an `#[output]` or an `#[input]`",
+            ));
+        }
+        kind = Some(attr_kind);
+    }
+    kind.ok_or_else(|| {
+        syn::Error::new_spanned(field, "expected an `#[output]` or `#[input]` attribute")
+    })
+}
+
+// methods generated on the graph besides the iterators named after the inputs
+const RESERVED_INPUT_NAMES: [&str; 3] = ["builder", "split_in_out", "stats"];
+
+fn has_node_type(elts: &[GraphElt], node: &syn::Type) -> bool {
+    let node_str = quote!(#node).to_string();
+    elts.iter().any(|elt| {
+        let prev = &elt.node;
+        quote!(#prev).to_string() == node_str
+    })
+}
+
+fn parse_graph(ast: &DeriveInput) -> syn::Result<GraphStructure> {
+    let fields = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields),
+            ..
+        }) => &fields.named,
+        syn::Data::Struct(syn::DataStruct { ref fields, .. }) => {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "`crusp_lazy_graph` expects a struct with named fields",
+            ));
+        }
+        syn::Data::Enum(syn::DataEnum { enum_token, .. }) => {
+            return Err(syn::Error::new_spanned(
+                enum_token,
+                "`crusp_lazy_graph` expects a struct with named fields",
+            ));
+        }
+        syn::Data::Union(syn::DataUnion { union_token, .. }) => {
+            return Err(syn::Error::new_spanned(
+                union_token,
+                "`crusp_lazy_graph` expects a struct with named fields",
+            ));
+        }
+    };
+    // the attributes are forwarded to the graph and its builder, whose fields implement no trait
+    if let Some(attr) = ast.attrs.iter().find(|attr| attr.path.is_ident("derive")) {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`crusp_lazy_graph` does not support `derive` on the graph",
+        ));
+    }
+    let mut outs: Vec<GraphElt> = Vec::new();
+    let mut ins = Vec::new();
+    for field in fields.iter() {
+        match field_kind(field)? {
+            FieldKind::Output(options) => {
+                let out = field_to_graph_elt(field, *options)?;
+                // impls are selected by output node type
+                if has_node_type(&outs, &out.node) {
+                    return Err(syn::Error::new_spanned(
+                        &out.node,
+                        "the outputs must have distinct node types",
+                    ));
+                }
+                outs.push(out);
+            }
+            FieldKind::Input => {
+                let input = field_to_graph_elt(field, OutputOptions::default())?;
+                // each input names an iterator method of the graph
+                if RESERVED_INPUT_NAMES.iter().any(|name| input.ident == name) {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        format!("`{}` is already a method of the graph, rename the input", input.ident),
+                    ));
+                }
+                // impls are selected by input node type
+                if has_node_type(&ins, &input.node) {
+                    return Err(syn::Error::new_spanned(
+                        &input.node,
+                        "the inputs must have distinct node types",
+                    ));
+                }
+                ins.push(input);
+            }
+        }
+    }
+    if outs.is_empty() {
+        return Err(syn::Error::new(ast.ident.span(), "missing an `#[output]` field"));
+    }
+    if ins.is_empty() {
+        return Err(syn::Error::new(ast.ident.span(), "missing an `#[input]` field"));
+    }
+    Ok(GraphStructure {
+        ident: ast.ident.clone(),
+        outs,
+        ins,
+    })
+}
+
+// `props` -> `Props`, `my_views` -> `MyViews`
+fn camel_case(ident: &syn::Ident) -> syn::Ident {
+    let name: String = ident
+        .to_string()
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    syn::Ident::new(&name, ident.span())
+}
+
+fn rev_ident(out: &GraphElt, input: &GraphElt) -> syn::Ident {
+    syn::Ident::new(&format!("__crusp__rev_{}__{}", out.ident, input.ident), span!())
+}
+
+fn delta_ident(out: &GraphElt, input: &GraphElt) -> syn::Ident {
+    syn::Ident::new(&format!("__crusp__delta_{}__{}", out.ident, input.ident), span!())
+}
+
+fn with_type_param(generics: &syn::Generics, param: &str) -> syn::Generics {
+    let mut generics = generics.clone();
+    let param = syn::Ident::new(param, span!());
+    generics.params.push(syn::GenericParam::Type(param.into()));
+    generics
 }
 
-// TODO(vincent): check if item is a DataStruct
 #[proc_macro_attribute]
-pub fn crusp_lazy_graph(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn crusp_lazy_graph(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr as GraphOptions);
     let ast = parse_macro_input!(item as DeriveInput);
     //eprintln!("{:#?}", ast);
-    let data = if let syn::Data::Struct(ref data) = ast.data {
-        data
-    } else {
-        unimplemented!()
+    let graph = match parse_graph(&ast) {
+        Ok(graph) => graph,
+        Err(err) => return err.to_compile_error().into(),
     };
-    // get visibility
-    let ident = ast.ident.clone();
+    // the graph and its builder keep the visibility and attributes of the annotated struct
+    let vis = &ast.vis;
+    let attrs = &ast.attrs;
     let ident_builder = format!("{}Builder", ast.ident);
-    let graph_ident_builder = syn::Ident::new(&ident_builder, span!());
-    let fields = if let syn::Fields::Named(ref fields) = data.fields {
-        &fields.named
+    let builder_name = syn::Ident::new(&ident_builder, span!());
+    //eprintln!("{:#?}", graph);
+
+    let graph_name = graph.ident;
+    // impls are selected by node type, so the node types of the outputs, resp. of the inputs,
+    // must not be able to unify with each other
+    let generics = &ast.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // the graph and its builder as types, with their generic arguments
+    let graph_ident = quote!(#graph_name #ty_generics);
+    let graph_ident_builder = quote!(#builder_name #ty_generics);
+    // impls with their own generic parameter extend the where clause of the graph
+    let where_preds = where_clause.map(|where_clause| {
+        let preds = where_clause.predicates.iter();
+        quote!(#(#preds,)*)
+    });
+    let visitor_generics = with_type_param(generics, "__CruspVisitor");
+    let (visitor_impl_generics, _, _) = visitor_generics.split_for_impl();
+    let look_generics = with_type_param(generics, "__CruspLook");
+    let (look_impl_generics, _, _) = look_generics.split_for_impl();
+    // unused lifetimes of the graph are kept by a marker
+    let lifetimes: Vec<_> = generics.lifetimes().map(|def| def.lifetime.clone()).collect();
+    let marker = quote!(::std::marker::PhantomData<(#(&#lifetimes (),)*)>);
+    let outs = &graph.outs;
+    let ins = &graph.ins;
+    let out_idents: Vec<_> = outs.iter().map(|out| out.ident.clone()).collect();
+    let out_nodes: Vec<_> = outs.iter().map(|out| out.node.clone()).collect();
+    let out_events: Vec<_> = outs.iter().map(|out| out.event.clone()).collect();
+    // the scheduler of the graph is used by the outputs without their own, the default one otherwise
+    let out_scheds: Vec<_> = outs
+        .iter()
+        .map(|out| {
+            let sched = out.options.scheduler.as_ref().or(options.scheduler.as_ref());
+            sched.map(|sched| quote!(, #sched))
+        })
+        .collect();
+    let out_vars: Vec<_> = outs
+        .iter()
+        .map(|out| syn::Ident::new(&format!("__crusp__out_{}", out.ident), span!()))
+        .collect();
+    let variants: Vec<_> = outs.iter().map(|out| camel_case(&out.ident)).collect();
+    let in_idents: Vec<_> = ins.iter().map(|field| field.ident.clone()).collect();
+    let in_nodes: Vec<_> = ins.iter().map(|field| field.node.clone()).collect();
+    let in_events: Vec<_> = ins.iter().map(|field| field.event.clone()).collect();
+    let rev_idents: Vec<_> = outs
+        .iter()
+        .flat_map(|out| ins.iter().map(move |input| rev_ident(out, input)))
+        .collect();
+    let delta_idents: Vec<_> = outs
+        .iter()
+        .flat_map(|out| ins.iter().map(move |input| delta_ident(out, input)))
+        .collect();
+
+    // only the output popped last has a running node
+    let clear_others: Vec<_> = outs
+        .iter()
+        .map(|out| {
+            let others = outs.iter().filter(|other| other.ident != out.ident).map(|other| &other.ident);
+            quote!(#(self.#others.clear_running();)*)
+        })
+        .collect();
+    // pops an output node and drops the deltas it did not visit
+    let out_pops: Vec<_> = outs
+        .iter()
+        .zip(clear_others.iter())
+        .map(|(out, clear_others)| {
+            let out_ident = &out.ident;
+            let out_deltas: Vec<_> = ins.iter().map(|input| delta_ident(out, input)).collect();
+            quote!({
+                #clear_others
+                let __crusp__popped = self.#out_ident.pop();
+                if let Some((__crusp__node, _)) = __crusp__popped {
+                    #(self.#out_deltas.clear(&__crusp__node);)*
+                }
+                __crusp__popped
+            })
+        })
+        .collect();
+
+    // The inputs of a graph with a single output link to it directly,
+    // otherwise through an enum with one variant per output.
+    let single_output = outs.len() == 1;
+    let link_name = syn::Ident::new(&format!("{}Link", graph_name), span!());
+    let link_ident = if single_output {
+        quote!(::crusp_graph::OutCostEventLink<#(#out_nodes)*, #(#out_events)*>)
+    } else {
+        quote!(#link_name #ty_generics)
+    };
+    // the enums only use the generic parameters of the outputs, the others are kept by an uninhabited variant
+    let type_params: Vec<_> = generics.type_params().map(|param| param.ident.clone()).collect();
+    let (marker_variant, link_marker_arm) = if lifetimes.is_empty() && type_params.is_empty() {
+        (None, None)
     } else {
-        unimplemented!()
+        (
+            Some(quote!(
+                #[doc(hidden)]
+                __CruspMarker(
+                    ::std::marker::PhantomData<(#(&#lifetimes (),)* #(#type_params,)*)>,
+                    ::std::convert::Infallible
+                ),
+            )),
+            Some(quote!(
+                #link_name::__CruspMarker(_, __crusp__never) => match *__crusp__never {},
+            )),
+        )
+    };
+    // dispatches `__crusp__link` to the outputs, `bodies` use the output link `__crusp__out`
+    let dispatch = |bodies: Vec<proc_macro2::TokenStream>| {
+        if single_output {
+            quote!({
+                let __crusp__out = __crusp__link;
+                #(#bodies)*
+            })
+        } else {
+            quote!(
+                match __crusp__link {
+                    #(#link_name::#variants(__crusp__out) => { #bodies })*
+                    #link_marker_arm
+                }
+            )
+        }
     };
-    let (out, ins): (Vec<_>, Vec<_>) = fields
+    // wraps the link `__crusp__out` to the output `variant`
+    let wrap_link = |variant: &syn::Ident| {
+        if single_output {
+            quote!(__crusp__out)
+        } else {
+            quote!(#link_name::#variant(__crusp__out))
+        }
+    };
+    // the links to the other outputs are skipped by the subscribers of an output
+    let out_links: Vec<_> = variants
         .iter()
-        .partition(|field| field.attrs[0].path.segments[0].ident == "output");
-    if out.len() != 1 {
-        panic!()
-    }
-    if ins.is_empty() {
-        panic!()
-    }
-    let out = out
-        .into_iter()
-        .map(|field| field_to_graph_elt(field))
-        .next()
-        .expect("Exactly one element");
-    let ins: Vec<_> = ins
-        .into_iter()
-        .map(|field| field_to_graph_elt(field))
+        .map(|variant| {
+            dispatch(
+                variants
+                    .iter()
+                    .map(|other| if other == variant { quote!(Some(__crusp__out)) } else { quote!(None) })
+                    .collect(),
+            )
+        })
         .collect();
-    let graph = GraphStructure { ident, out, ins };
-    //eprintln!("{:#?}", graph);
+    let link_doc = format!("Link of an input node of `{}` to a node of the output of the same name.", graph_name);
+    let link_enum = if single_output {
+        None
+    } else {
+        Some(quote!(
+            #[doc = #link_doc]
+            #vis enum #link_name #generics #where_clause
+            {
+                #(#variants(::crusp_graph::OutCostEventLink<#out_nodes, #out_events>),)*
+                #marker_variant
+            }
 
-    let graph_ident = graph.ident;
-    let (out_ident, out_node, out_event) = (graph.out.ident, graph.out.node, graph.out.event);
+            impl #impl_generics ::std::clone::Clone for #link_ident #where_clause
+            {
+                fn clone(&self) -> Self {
+                    match self {
+                        #(#link_name::#variants(__crusp__link) => #link_name::#variants(__crusp__link.clone()),)*
+                        #link_marker_arm
+                    }
+                }
+            }
 
-    let out_field = quote!(
-        #out_ident: ::crusp_graph::HandlerOutput<#out_node, #out_event>
-    );
-    let out_builder_field = quote!(
-        #out_ident: ::crusp_graph::HandlerOutputBuilder<#out_node, #out_event>
-    );
-    let in_builder_fields: Vec<_> = graph
-        .ins
+            #(impl #impl_generics ::crusp_graph::OutputLink<#out_nodes, #out_events> for #link_ident #where_clause
+            {
+                fn out_link(&self) -> Option<&::crusp_graph::OutCostEventLink<#out_nodes, #out_events>> {
+                    let __crusp__link = self;
+                    #out_links
+                }
+            })*
+        ))
+    };
+
+    let out_builder_fields: Vec<_> = outs
         .iter()
-        .map(|field| {
-            let (ident, node, event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
+        .zip(out_scheds.iter())
+        .map(|(out, out_sched)| {
+            let (ident, node, event) = (&out.ident, &out.node, &out.event);
             quote!(
-                #ident: ::crusp_graph::LazyInputEventGraphBuilder<
-                    #node,
-                    #event,
-                    ::crusp_graph::OutCostEventLink<#out_node, #out_event>
-                >
+                #ident: ::crusp_graph::HandlerOutputBuilder<#node, #event #out_sched>
             )
         })
         .collect();
-    let in_rev_builder_fields: Vec<_> = graph
-        .ins
+    let out_fields: Vec<_> = outs
         .iter()
-        .map(|field| {
-            let (ident, in_node, _event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
-            let ident_name = format!("__crusp__rev_{}", ident);
-            let ident = syn::Ident::new(&ident_name, span!());
-            let out_node = out_node.clone();
+        .zip(out_scheds.iter())
+        .map(|(out, out_sched)| {
+            let (ident, node, event) = (&out.ident, &out.node, &out.event);
             quote!(
-                #ident: ::crusp_graph::AdjacentListGraphBuilder<
-                    #out_node,
-                    #in_node,
-                >
+                #ident: ::crusp_graph::HandlerOutput<#node, #event #out_sched>
             )
         })
         .collect();
-    let in_fields: Vec<_> = graph
-        .ins
+    let in_builder_fields: Vec<_> = ins
         .iter()
         .map(|field| {
-            let (ident, node, event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
+            let (ident, node, event) = (&field.ident, &field.node, &field.event);
             quote!(
-                #ident: ::crusp_graph::LazyInputEventHandler<
-                    #node,
-                    #event,
-                    ::crusp_graph::OutCostEventLink<#out_node, #out_event>
-                >
+                #ident: ::crusp_graph::LazyInputEventGraphBuilder<#node, #event, #link_ident>
             )
         })
         .collect();
-    let in_rev_fields: Vec<_> = graph
-        .ins
+    let in_fields: Vec<_> = ins
         .iter()
         .map(|field| {
-            let (ident, in_node, _event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
-            let ident_name = format!("__crusp__rev_{}", ident);
-            let ident = syn::Ident::new(&ident_name, span!());
-            let out_node = out_node.clone();
+            let (ident, node, event) = (&field.ident, &field.node, &field.event);
             quote!(
-                #ident: ::std::rc::Rc<::crusp_graph::AdjacentListGraph<
-                    #out_node,
-                    #in_node,
-                >>
+                #ident: ::crusp_graph::LazyInputEventHandler<#node, #event, #link_ident>
             )
         })
         .collect();
-    let in_events_handler: Vec<_> = graph
-        .ins
+    let rev_builder_fields: Vec<_> = outs
+        .iter()
+        .flat_map(|out| {
+            ins.iter().map(move |input| {
+                let (ident, out_node, in_node) = (rev_ident(out, input), &out.node, &input.node);
+                quote!(
+                    #ident: ::crusp_graph::AdjacentListGraphBuilder<#out_node, #in_node>
+                )
+            })
+        })
+        .collect();
+    let rev_fields: Vec<_> = outs
+        .iter()
+        .flat_map(|out| {
+            ins.iter().map(move |input| {
+                let (ident, out_node, in_node) = (rev_ident(out, input), &out.node, &input.node);
+                quote!(
+                    #ident: ::std::rc::Rc<::crusp_graph::AdjacentListGraph<#out_node, #in_node>>
+                )
+            })
+        })
+        .collect();
+    let delta_fields: Vec<_> = outs
+        .iter()
+        .flat_map(|out| {
+            ins.iter().map(move |input| {
+                let ident = delta_ident(out, input);
+                let (out_node, in_node, in_event) = (&out.node, &input.node, &input.event);
+                quote!(
+                    #ident: ::crusp_graph::DeltaStore<#out_node, #in_node, #in_event>
+                )
+            })
+        })
+        .collect();
+
+    let in_events_handler: Vec<_> = ins
         .iter()
         .map(|field| {
-            let (ident, node, event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
-            let in_node = node;
-            let in_event = event;
+            let (ident, in_node, in_event) = (&field.ident, &field.node, &field.event);
             quote!(
-                impl ::crusp_graph::InputEventHandler<#in_node, #in_event> for #graph_ident
+                impl #impl_generics ::crusp_graph::InputEventHandler<#in_node, #in_event> for #graph_ident #where_clause
                 {
                     #[allow(clippy::inline_always)]
                     #[inline(always)]
                      fn notify(&mut self, in_node: &#in_node, in_event: &#in_event) -> bool {
-                         if self.#ident.notify(in_node, in_event) {
+                         let time = self.__crusp__clock + 1;
+                         if self.#ident.notify_at(in_node, in_event, time) {
+                            self.__crusp__clock = time;
                             true
                          } else {
                              false
                          }
                     }
                 }
+
+                impl #impl_generics ::crusp_graph::InputChangeTracker<#in_node> for #graph_ident #where_clause
+                {
+                    fn last_change(&self, in_node: &#in_node) -> u64 {
+                        ::crusp_graph::InputChangeTracker::last_change(&self.#ident, in_node)
+                    }
+                    fn peek_change(&mut self, in_node: &#in_node) -> bool {
+                        ::crusp_graph::InputChangeTracker::peek_change(&mut self.#ident, in_node)
+                    }
+                }
+
+                impl #impl_generics ::crusp_graph::WeightedDegree<#in_node> for #graph_ident #where_clause
+                {
+                    fn weighted_degree(&self, in_node: &#in_node) -> u64 {
+                        ::crusp_graph::WeightedDegree::weighted_degree(&self.#ident, in_node)
+                    }
+                }
             )
         })
         .collect();
-    let inout_events_handler: Vec<_> = graph.ins.iter()
-        .map(|field| {
-            let graph_ident_builder = graph_ident_builder.clone();
-            let (in_ident, node, event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
-            let rev_ident = format!("__crusp__rev_{}", in_ident);
-            let rev_ident = syn::Ident::new(&rev_ident, span!());
-            let out_node = out_node.clone();
-            let out_event = out_event.clone();
-            let in_node = node;
-            let in_event = event;
-            let out_ident = out_ident.clone();
+    let inout_events_handler: Vec<_> = outs
+        .iter()
+        .zip(variants.iter())
+        .flat_map(|(out, variant)| {
+            ins.iter().map(move |input| (out, variant, input))
+        })
+        .map(|(out, variant, input)| {
+            let rev_ident = rev_ident(out, input);
+            let (out_ident, out_node, out_event) = (&out.ident, &out.node, &out.event);
+            let (in_ident, in_node, in_event) = (&input.ident, &input.node, &input.event);
+            let link = wrap_link(variant);
             quote!(
-                impl ::crusp_graph::InOutEventHandlerBuilder<#out_node, #out_event, #in_node, #in_event>
-                    for #graph_ident_builder
+                impl #impl_generics ::crusp_graph::InOutEventHandlerBuilder<#out_node, #out_event, #in_node, #in_event>
+                    for #graph_ident_builder #where_clause
                 {
                     fn add_event(&mut self, out_node: &#out_node, out_event: &#out_event, in_node: &#in_node, in_event: &#in_event, cost: i64) {
-                        let out = <::crusp_graph::OutCostEventLink<#out_node, #out_event>>::new(
+                        let __crusp__out = <::crusp_graph::OutCostEventLink<#out_node, #out_event>>::new(
                             *out_node,
                             *out_event,
                             cost
                         );
+                        let out = #link;
                         self.#in_ident.add_event(*in_node, *in_event, out);
-                        self.#rev_ident.add_node(out_node, in_node);
+                        ::crusp_graph::GraphBuilder::add_node(&mut self.#rev_ident, out_node, in_node);
                         self.#out_ident.add_node(*out_node);
                     }
                 }
-            )
-        })
-        .collect();
-    let graph_builder_impl = {
-        let graph_ident_builder = graph_ident_builder.clone();
-        let graph_ident = graph_ident.clone();
-        let out_ident = out_ident.clone();
-        let out_node = out_node.clone();
-        let out_event = out_event.clone();
-        let in_idents: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.ident.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_rev_idents: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.ident.clone();
-                let rev_field = format!("__crusp__rev_{}", field);
-                let rev_field = syn::Ident::new(&rev_field, span!());
-                quote!(#rev_field)
-            })
-            .collect();
-        let in_idents2 = in_idents.clone();
-        let in_idents3 = in_idents.clone();
-        let out_events: Vec<_> = std::iter::repeat(&out_event)
-            .clone()
-            .take(in_rev_idents.len())
-            .collect();
-        let out_nodes: Vec<_> = std::iter::repeat(&out_node)
-            .clone()
-            .take(in_idents.len())
-            .collect();
-        let in_nodes: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.node.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_events: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.event.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_nodes2 = in_nodes.clone();
-        let in_events2 = in_events.clone();
-        let out_events2 = out_events.clone();
-        let in_rev_idents2 = in_rev_idents.clone();
-        let in_rev_idents3 = in_rev_idents.clone();
-        let in_rev_nodes = in_nodes.clone();
-
-        quote!(
-            impl #graph_ident_builder
-            {
-                pub fn new() -> Self {
-                    #graph_ident_builder {
-                        #(#in_idents: <::crusp_graph::LazyInputEventHandler<#in_nodes, #in_events, ::crusp_graph::OutCostEventLink<#out_node, #out_events>>>::builder()),*,
-                        #(#in_rev_idents: <::crusp_graph::AdjacentListGraph<#out_nodes,#in_rev_nodes>>::builder()),*,
-                        #out_ident: <::crusp_graph::HandlerOutput<#out_node, #out_event>>::builder(),
+
+                impl #impl_generics ::crusp_graph::InOutEventHandlerBuilder<#out_node, #out_event, #in_node, #in_event>
+                    for #graph_ident #where_clause
+                {
+                    fn add_event(&mut self, out_node: &#out_node, out_event: &#out_event, in_node: &#in_node, in_event: &#in_event, cost: i64) {
+                        let __crusp__out = <::crusp_graph::OutCostEventLink<#out_node, #out_event>>::new(
+                            *out_node,
+                            *out_event,
+                            cost
+                        );
+                        let out = #link;
+                        self.#in_ident.add_event(*in_node, *in_event, out);
+                        ::crusp_graph::GraphBuilder::add_node(
+                            ::std::rc::Rc::make_mut(&mut self.#rev_ident),
+                            out_node,
+                            in_node
+                        );
+                        self.#out_ident.add_node(*out_node);
                     }
                 }
 
-                pub fn finalize(self) -> #graph_ident {
-                    #graph_ident {
-                        #(#in_idents2: <::crusp_graph::LazyInputEventHandler<#in_nodes2, #in_events2, ::crusp_graph::OutCostEventLink<#out_node, #out_events2>>>::new(self.#in_idents3.finalize())),*,
-                        #(#in_rev_idents2: ::std::rc::Rc::new(self.#in_rev_idents3.finalize())),*,
-                        #out_ident: self.#out_ident.finalize(),
+                impl #impl_generics ::crusp_graph::VisitOutputsNode<#out_node, #in_node>
+                    for #graph_ident #where_clause
+                {
+                    fn visit_in_nodes<__CruspVisitor>(&self, out_node: &#out_node, visitor: &mut __CruspVisitor)
+                        where __CruspVisitor: ::crusp_graph::VisitMut<#in_node>
+                    {
+                        ::crusp_graph::VisitOutputsNode::visit_in_nodes(&*self.#rev_ident, out_node, visitor);
                     }
                 }
-            }
-        )
-    };
-    let graph_impl = {
-        let graph_ident_builder = graph_ident_builder.clone();
-        let graph_ident = graph_ident.clone();
-        let out_ident = out_ident.clone();
-        let out_node = out_node.clone();
-        let out_event = out_event.clone();
-        let in_idents: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.ident.clone();
-                quote!(#field)
-            })
-            .collect();
-        let out_events: Vec<_> = std::iter::repeat(&out_event)
-            .clone()
-            .take(in_idents.len())
-            .collect();
-        let in_nodes: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.node.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_events: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.event.clone();
-                quote!(#field)
-            })
-            .collect();
 
-        quote!(
-            impl  #graph_ident
-            {
-                pub fn builder() -> #graph_ident_builder {
-                    <#graph_ident_builder>::new()
-                }
-
-                #[allow(clippy::type_complexity)]
-                #[inline]
-                pub fn split_in_out(&mut self) -> (
-                        &mut ::crusp_graph::HandlerOutput<#out_node, #out_event>,
-                        #(&mut ::crusp_graph::LazyInputEventHandler<
-                            #in_nodes, #in_events,
-                            ::crusp_graph::OutCostEventLink<#out_node, #out_events>>
-                        ),*
-                    )
+                impl #impl_generics ::crusp_graph::InNodes<#out_node, #in_node>
+                    for #graph_ident #where_clause
                 {
-                    (
-                        unsafe{ &mut *((&mut self.#out_ident) as *mut _)},
-                        #(unsafe{ &mut *((&mut self.#in_idents) as *mut _)}),*
-                    )
+                    fn in_nodes(&self, out_node: &#out_node) -> ::crusp_graph::InNodesIter<'_, #in_node> {
+                        self.#rev_ident.in_nodes(out_node)
+                    }
                 }
-            }
-        )
-    };
-    let impl_pop = {
-        let out_ident = out_ident.clone();
-        let out_node = out_node.clone();
-        let out_event = out_event.clone();
-        let in_idents: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.ident.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_idents2: Vec<_> = in_idents.clone();
 
-        quote!(
-            impl ::crusp_graph::OutputEventHandler<#out_node, #out_event> for #graph_ident
-            {
-                fn collect(&mut self, ignored: Option<OutNode>) {
-                    let (__crusp__outs, #(#in_idents),*) = self.split_in_out();
-                    #(#in_idents2.trigger_events(|__crusp__out| __crusp__outs.collect_out_event(__crusp__out, ignored)));*;
+                impl #impl_generics ::crusp_graph::Subscribers<#in_node, #in_event, #out_node, #out_event>
+                    for #graph_ident #where_clause
+                {
+                    type Link = #link_ident;
+
+                    fn subscribers(&self, in_node: &#in_node)
+                        -> ::crusp_graph::SubscribersIter<'_, #in_event, #link_ident, #out_node, #out_event>
+                    {
+                        ::crusp_graph::SubscribersIter::new(self.#in_ident.links(in_node))
+                    }
                 }
-                fn collect_and_pop(&mut self, ignored: Option<OutNode>) -> Option<(#out_node, #out_event)> {
-                    self.collect(ignored);
-                    self.#out_ident.pop()
+
+                impl #impl_generics ::crusp_graph::VisitChangedOutputsNode<#out_node, #in_node>
+                    for #graph_ident #where_clause
+                {
+                    fn visit_changed_in_nodes<__CruspVisitor>(&self, out_node: &#out_node, since: u64, visitor: &mut __CruspVisitor)
+                        where __CruspVisitor: ::crusp_graph::VisitMut<#in_node>
+                    {
+                        ::crusp_graph::VisitOutputsNode::visit_in_nodes(
+                            &*self.#rev_ident,
+                            out_node,
+                            &mut ::crusp_graph::ChangedVisitor {
+                                tracker: &self.#in_ident,
+                                since,
+                                visitor,
+                            }
+                        );
+                    }
                 }
+            )
         })
-    };
-    let impl_pop_look = {
-        let out_ident = out_ident;
-        let out_node = out_node.clone();
-        let out_event = out_event;
-        let in_idents: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.ident.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_nodes: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.node.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_events: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.event.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_idents2: Vec<_> = in_idents.clone();
+        .collect();
+    let graph_builder_impl = quote!(
+        impl #impl_generics #graph_ident_builder #where_clause
+        {
+            #vis fn new() -> Self {
+                #builder_name {
+                    #(#out_idents: ::crusp_graph::HandlerOutputBuilder::new(),)*
+                    #(#in_idents: ::crusp_graph::LazyInputEventGraphBuilder::new(),)*
+                    #(#rev_idents: ::crusp_graph::AdjacentListGraph::builder(),)*
+                    __crusp__marker: ::std::marker::PhantomData,
+                }
+            }
 
-        quote!(
-            impl <Look> ::crusp_graph::OutputEventHandlerLookup<#out_node, #out_event, Look> for #graph_ident
-               where
-               #(Look: LookEvent<#in_nodes, #in_events>),*,
-            {
-                fn collect_look(&mut self, look: &mut Look, ignored: Option<OutNode>) {
-                    let (__crusp__outs, #(#in_idents),*) = self.split_in_out();
-                    #(#in_idents2.trigger_look_events(|__crusp__out| __crusp__outs.collect_out_event(__crusp__out, ignored), look));*;
+            #vis fn finalize(self) -> #graph_ident {
+                #graph_name {
+                    #(#out_idents: self.#out_idents.finalize(),)*
+                    #(#in_idents: ::crusp_graph::LazyInputEventHandler::new(self.#in_idents.finalize()),)*
+                    #(#rev_idents: ::std::rc::Rc::new(self.#rev_idents.finalize()),)*
+                    #(#delta_idents: ::crusp_graph::DeltaStore::new(),)*
+                    __crusp__clock: 0,
+                    __crusp__marker: ::std::marker::PhantomData,
                 }
-                fn collect_look_and_pop(&mut self, look: &mut Look, ignored: Option<OutNode>) -> Option<(#out_node, #out_event)> {
-                    self.collect_look(look, ignored);
-                    self.#out_ident.pop()
+            }
+        }
+    );
+    // `graph.input(&out_node)` iterates over the nodes of `input` linked to `out_node`
+    let in_nodes_methods: Vec<_> = ins
+        .iter()
+        .map(|field| {
+            let (ident, in_node) = (&field.ident, &field.node);
+            let doc = format!("Nodes of `{}` linked to `out_node`, in increasing order.", ident);
+            quote!(
+                #[doc = #doc]
+                pub fn #ident<__CruspOut>(&self, out_node: &__CruspOut) -> ::crusp_graph::InNodesIter<'_, #in_node>
+                    where Self: ::crusp_graph::InNodes<__CruspOut, #in_node>,
+                          __CruspOut: ::crusp_graph::GraphNode,
+                {
+                    ::crusp_graph::InNodes::in_nodes(self, out_node)
                 }
+            )
         })
-    };
-    let impl_visit_all = {
-        let graph_ident = graph_ident.clone();
-        let out_node = out_node.clone();
-        let in_nodes: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.node.clone();
-                quote!(#field)
-            })
-            .collect();
-        let in_rev_idents: Vec<_> = graph
-            .ins
-            .iter()
-            .map(|field| {
-                let field = field.ident.clone();
-                let rev_field = format!("__crusp__rev_{}", field);
-                let rev_field = syn::Ident::new(&rev_field, span!());
-                quote!(#rev_field)
-            })
-            .collect();
+        .collect();
+    // the degree of an output node sums its degrees towards each input
+    let out_stats: Vec<_> = outs
+        .iter()
+        .map(|out| {
+            let (name, out_node) = (out.ident.to_string(), &out.node);
+            let out_revs: Vec<_> = ins.iter().map(|input| rev_ident(out, input)).collect();
+            quote!(
+                (#name, ::crusp_graph::Distribution::from_counts(
+                    (0..0 #(.max(self.#out_revs.sources()))*).map(|__crusp__idx| {
+                        let out_node = <#out_node as ::std::convert::From<usize>>::from(__crusp__idx);
+                        0 #(+ self.#out_revs.degree(&out_node))*
+                    })
+                ))
+            )
+        })
+        .collect();
+    let in_names: Vec<_> = ins.iter().map(|field| field.ident.to_string()).collect();
+    let graph_impl = quote!(
+        impl #impl_generics #graph_ident #where_clause
+        {
+            pub fn builder() -> #graph_ident_builder {
+                <#graph_ident_builder>::new()
+            }
 
-        quote!(
-            impl <Visitor> ::crusp_graph::VisitAllOutputsNode<#out_node, Visitor> for #graph_ident
-               where
-               #(Visitor: VisitMut<#in_nodes>),*,
-            {
-                fn visit_all_in_nodes(&self, out_node: &#out_node, visitor: &mut Visitor)
-                {
-                    #(self.#in_rev_idents.visit_in_nodes(out_node, visitor));*;
+            #(#in_nodes_methods)*
+
+            /// Node, edge and degree statistics of each output and input.
+            pub fn stats(&self) -> ::crusp_graph::GraphStats {
+                ::crusp_graph::GraphStats {
+                    outputs: ::std::vec![#(#out_stats),*],
+                    inputs: ::std::vec![#((#in_names, self.#in_idents.stats())),*],
                 }
             }
-        )
-    };
-    let impl_visitors: Vec<_> = graph
-        .ins
+
+            #[allow(clippy::type_complexity)]
+            #[inline]
+            pub fn split_in_out(&mut self) -> (
+                    #(&mut ::crusp_graph::HandlerOutput<#out_nodes, #out_events #out_scheds>,)*
+                    #(&mut ::crusp_graph::LazyInputEventHandler<#in_nodes, #in_events, #link_ident>),*
+                )
+            {
+                // disjoint field borrows
+                (
+                    #(&mut self.#out_idents,)*
+                    #(&mut self.#in_idents),*
+                )
+            }
+        }
+    );
+    let impl_outputs: Vec<_> = outs
         .iter()
-        .map(|field| {
-            let (in_ident, node, _event) =
-                (field.ident.clone(), field.node.clone(), field.event.clone());
-            let rev_ident = format!("__crusp__rev_{}", in_ident);
-            let rev_ident = syn::Ident::new(&rev_ident, span!());
-            let out_node = out_node.clone();
-            let in_node = node;
+        .enumerate()
+        .map(|(out_idx, out)| {
+            let (out_ident, out_node, out_event) = (&out.ident, &out.node, &out.event);
+            let out_revs: Vec<_> = ins.iter().map(|input| rev_ident(out, input)).collect();
+            let out_deltas: Vec<_> = ins.iter().map(|input| delta_ident(out, input)).collect();
+            let pop = &out_pops[out_idx];
+            let clear_others = &clear_others[out_idx];
+            // `ignored` only applies to this output
+            let ignored: Vec<_> = (0..outs.len())
+                .map(|idx| if idx == out_idx { quote!(ignored) } else { quote!(None) })
+                .collect();
+            let collect = dispatch(
+                out_vars
+                    .iter()
+                    .zip(ignored.iter())
+                    .map(|(out_var, ignored)| quote!(#out_var.collect_out_event(__crusp__out, #ignored);))
+                    .collect(),
+            );
+            // the deltas of an input are recorded for every output, each delta store is cleared by its own pops
+            let collect_deltas: Vec<_> = ins
+                .iter()
+                .map(|input| {
+                    let in_ident = &input.ident;
+                    let delta_vars: Vec<_> = outs
+                        .iter()
+                        .map(|out| syn::Ident::new(&format!("__crusp__delta_{}", out.ident), span!()))
+                        .collect();
+                    let in_deltas: Vec<_> = outs.iter().map(|out| delta_ident(out, input)).collect();
+                    let record = dispatch(
+                        out_vars
+                            .iter()
+                            .zip(delta_vars.iter())
+                            .zip(ignored.iter())
+                            .map(|((out_var, delta_var), ignored)| {
+                                quote!(
+                                    if #out_var.try_collect_out_event(__crusp__out, #ignored) {
+                                        #delta_var.record(&__crusp__out.node(), __crusp__node, __crusp__event);
+                                    }
+                                )
+                            })
+                            .collect(),
+                    );
+                    quote!({
+                        #(let #delta_vars = &mut self.#in_deltas;)*
+                        self.#in_ident.trigger_delta_events(|__crusp__node, __crusp__event, __crusp__link| #record);
+                    })
+                })
+                .collect();
+            let handler = quote!(<Self as ::crusp_graph::OutputEventHandler<#out_node, #out_event>>);
+            let look_handler = quote!(<Self as ::crusp_graph::OutputEventHandlerLookup<#out_node, #out_event, __CruspLook>>);
             quote!(
-                impl ::crusp_graph::VisitOutputsNode<#out_node, #in_node>
-                    for #graph_ident
+                impl #impl_generics ::crusp_graph::OutputEventHandler<#out_node, #out_event> for #graph_ident #where_clause
+                {
+                    fn collect(&mut self, ignored: Option<#out_node>) {
+                        let (#(#out_vars,)* #(#in_idents),*) = self.split_in_out();
+                        #(#in_idents.trigger_events(|__crusp__link| #collect);)*
+                    }
+                    fn collect_and_pop(&mut self, ignored: Option<#out_node>) -> Option<(#out_node, #out_event)> {
+                        #handler::collect(self, ignored);
+                        #pop
+                    }
+                    fn collect_ignoring(&mut self, ignored: &[#out_node]) {
+                        self.#out_ident.set_ignored(ignored, true);
+                        #handler::collect(self, None);
+                        self.#out_ident.set_ignored(ignored, false);
+                    }
+                    fn collect_and_pop_ignoring(&mut self, ignored: &[#out_node]) -> Option<(#out_node, #out_event)> {
+                        #handler::collect_ignoring(self, ignored);
+                        #pop
+                    }
+                }
+
+                impl #visitor_impl_generics ::crusp_graph::OutputDeltaEventHandler<#out_node, #out_event, __CruspVisitor> for #graph_ident
+                   where #where_preds
+                   #(__CruspVisitor: ::crusp_graph::VisitMut<(#in_nodes, #in_events)>),*,
+                {
+                    fn collect_delta_and_pop(&mut self, ignored: Option<#out_node>, visitor: &mut __CruspVisitor) -> Option<(#out_node, #out_event)> {
+                        #(let #out_vars = &mut self.#out_idents;)*
+                        #(#collect_deltas)*
+                        #clear_others
+                        let (__crusp__node, __crusp__event) = self.#out_ident.pop()?;
+                        #(self.#out_deltas.visit_and_clear(&__crusp__node, visitor);)*
+                        Some((__crusp__node, __crusp__event))
+                    }
+                }
+
+                impl #look_impl_generics ::crusp_graph::OutputEventHandlerLookup<#out_node, #out_event, __CruspLook> for #graph_ident
+                   where #where_preds
+                   #(__CruspLook: ::crusp_graph::LookEvent<#in_nodes, #in_events>),*,
+                {
+                    fn collect_look(&mut self, look: &mut __CruspLook, ignored: Option<#out_node>) {
+                        let (#(#out_vars,)* #(#in_idents),*) = self.split_in_out();
+                        #(#in_idents.trigger_look_events(|__crusp__link| #collect, look);)*
+                    }
+                    fn collect_look_and_pop(&mut self, look: &mut __CruspLook, ignored: Option<#out_node>) -> Option<(#out_node, #out_event)> {
+                        #look_handler::collect_look(self, look, ignored);
+                        #pop
+                    }
+                }
+
+                impl #impl_generics ::crusp_graph::DisableOutputNode<#out_node> for #graph_ident #where_clause
+                {
+                    fn disable(&mut self, out_node: &#out_node) {
+                        ::crusp_graph::DisableOutputNode::disable(&mut self.#out_ident, out_node);
+                        #(self.#out_deltas.clear(out_node);)*
+                    }
+                    fn enable(&mut self, out_node: &#out_node) {
+                        ::crusp_graph::DisableOutputNode::enable(&mut self.#out_ident, out_node)
+                    }
+                    fn is_disabled(&self, out_node: &#out_node) -> bool {
+                        ::crusp_graph::DisableOutputNode::is_disabled(&self.#out_ident, out_node)
+                    }
+                }
+
+                impl #impl_generics ::crusp_graph::IdempotentOutputNode<#out_node> for #graph_ident_builder #where_clause
+                {
+                    fn set_idempotent(&mut self, out_node: &#out_node, idempotent: bool) {
+                        ::crusp_graph::IdempotentOutputNode::set_idempotent(&mut self.#out_ident, out_node, idempotent)
+                    }
+                    fn is_idempotent(&self, out_node: &#out_node) -> bool {
+                        ::crusp_graph::IdempotentOutputNode::is_idempotent(&self.#out_ident, out_node)
+                    }
+                }
+
+                impl #impl_generics ::crusp_graph::IdempotentOutputNode<#out_node> for #graph_ident #where_clause
                 {
-                    fn visit_in_nodes<Visitor>(&self, out_node: &#out_node, visitor: &mut Visitor)
-                        where Visitor: VisitMut<#in_node>
+                    fn set_idempotent(&mut self, out_node: &#out_node, idempotent: bool) {
+                        ::crusp_graph::IdempotentOutputNode::set_idempotent(&mut self.#out_ident, out_node, idempotent)
+                    }
+                    fn is_idempotent(&self, out_node: &#out_node) -> bool {
+                        ::crusp_graph::IdempotentOutputNode::is_idempotent(&self.#out_ident, out_node)
+                    }
+                }
+
+                impl #impl_generics ::crusp_graph::FailureHandler<#out_node> for #graph_ident #where_clause
+                {
+                    fn notify_failure(&mut self, out_node: &#out_node) {
+                        ::crusp_graph::FailureHandler::notify_failure(&mut self.#out_ident, out_node);
+                        #(::crusp_graph::VisitOutputsNode::visit_in_nodes(
+                            &*self.#out_revs,
+                            out_node,
+                            &mut ::crusp_graph::FailureVisitor(&mut self.#in_idents)
+                        );)*
+                    }
+                }
+
+                impl #impl_generics ::crusp_graph::OutputWeight<#out_node> for #graph_ident #where_clause
+                {
+                    fn weight(&self, out_node: &#out_node) -> u64 {
+                        ::crusp_graph::OutputWeight::weight(&self.#out_ident, out_node)
+                    }
+                }
+
+                impl #visitor_impl_generics ::crusp_graph::VisitAllOutputsNode<#out_node, __CruspVisitor> for #graph_ident
+                   where #where_preds
+                   #(__CruspVisitor: ::crusp_graph::VisitMut<#in_nodes>),*,
+                {
+                    fn visit_all_in_nodes(&self, out_node: &#out_node, visitor: &mut __CruspVisitor)
+                    {
+                        #(::crusp_graph::VisitOutputsNode::visit_in_nodes(&*self.#out_revs, out_node, visitor);)*
+                    }
+                }
+
+                impl #visitor_impl_generics ::crusp_graph::VisitAllChangedOutputsNode<#out_node, __CruspVisitor> for #graph_ident
+                   where #where_preds
+                   #(__CruspVisitor: ::crusp_graph::VisitMut<#in_nodes>),*,
+                {
+                    fn visit_all_changed_in_nodes(&self, out_node: &#out_node, since: u64, visitor: &mut __CruspVisitor)
                     {
-                        self.#rev_ident.visit_in_nodes(out_node, visitor);
+                        #(::crusp_graph::VisitOutputsNode::visit_in_nodes(
+                            &*self.#out_revs,
+                            out_node,
+                            &mut ::crusp_graph::ChangedVisitor {
+                                tracker: &self.#in_idents,
+                                since,
+                                visitor: &mut *visitor,
+                            }
+                        );)*
                     }
                 }
             )
         })
         .collect();
+    let impl_backtrack = quote!(
+        impl #impl_generics ::crusp_graph::Backtrackable for #graph_ident #where_clause
+        {
+            fn push_level(&mut self) {
+                #(::crusp_graph::Backtrackable::push_level(&mut self.#out_idents);)*
+                #(::crusp_graph::Backtrackable::push_level(&mut self.#in_idents);)*
+            }
+            fn pop_level(&mut self) {
+                #(::crusp_graph::Backtrackable::pop_level(&mut self.#out_idents);)*
+                #(::crusp_graph::Backtrackable::pop_level(&mut self.#in_idents);)*
+                // deltas are only meaningful until the next fixpoint
+                #(self.#delta_idents.clear_all();)*
+            }
+        }
+
+        impl #impl_generics ::crusp_graph::Timestamped for #graph_ident #where_clause
+        {
+            fn timestamp(&self) -> u64 {
+                self.__crusp__clock
+            }
+        }
+    );
+    // Graphs with several outputs also pop across them, tagged by a variant per output.
+    let impl_combined = if outs.len() > 1 {
+        let output_name = syn::Ident::new(&format!("{}Output", graph_name), span!());
+        let output_ident = quote!(#output_name #ty_generics);
+        let ranks: Vec<_> = outs.iter().map(|out| out.options.rank).collect();
+        let out_idxs: Vec<_> = (0..outs.len()).collect();
+        let out_idxs2 = out_idxs.clone();
+        let collect = dispatch(
+            out_vars
+                .iter()
+                .map(|out_var| quote!(#out_var.collect_out_event(__crusp__out, None);))
+                .collect(),
+        );
+        Some(quote!(
+            /// Output node and event popped from one of the outputs of the graph.
+            // only read by the users of the combined pop
+            #[allow(dead_code)]
+            #[derive(Clone, Copy, Debug)]
+            #vis enum #output_name #generics #where_clause
+            {
+                #(#variants(#out_nodes, #out_events),)*
+                #marker_variant
+            }
+
+            impl #impl_generics ::crusp_graph::CombinedOutputEventHandler<#output_ident> for #graph_ident #where_clause
+            {
+                fn collect_all(&mut self) {
+                    let (#(#out_vars,)* #(#in_idents),*) = self.split_in_out();
+                    #(#in_idents.trigger_events(|__crusp__link| #collect);)*
+                }
+                fn pop_any(&mut self) -> Option<#output_ident> {
+                    // output, rank and cost of the best front node
+                    let mut __crusp__best: Option<(usize, i64, i64)> = None;
+                    #(
+                        if let Some((_, __crusp__cost)) = self.#out_idents.peek() {
+                            let __crusp__better = match __crusp__best {
+                                Some((_, __crusp__rank, __crusp__best_cost)) => {
+                                    #ranks < __crusp__rank || (#ranks == __crusp__rank && __crusp__cost > __crusp__best_cost)
+                                }
+                                None => true,
+                            };
+                            if __crusp__better {
+                                __crusp__best = Some((#out_idxs, #ranks, __crusp__cost));
+                            }
+                        }
+                    )*
+                    let __crusp__popped = match __crusp__best {
+                        Some((__crusp__popped, _, _)) => __crusp__popped,
+                        None => {
+                            // all the outputs are empty, none of them is running
+                            #(self.#out_idents.clear_running();)*
+                            return None;
+                        }
+                    };
+                    match __crusp__popped {
+                        #(#out_idxs2 => #out_pops.map(|(__crusp__node, __crusp__event)| {
+                            #output_name::#variants(__crusp__node, __crusp__event)
+                        }),)*
+                        _ => None,
+                    }
+                }
+            }
+        ))
+    } else {
+        None
+    };
     let expanded = quote!(
-        struct #graph_ident_builder
+        #(#attrs)*
+        #vis struct #builder_name #generics #where_clause
         {
-            #out_builder_field,
-            #(#in_builder_fields),*,
-            #(#in_rev_builder_fields),*,
+            #(#out_builder_fields,)*
+            #(#in_builder_fields,)*
+            #(#rev_builder_fields,)*
+            __crusp__marker: #marker,
         }
 
-        struct #graph_ident
+        #(#attrs)*
+        #vis struct #graph_name #generics #where_clause
         {
-            #out_field,
-            #(#in_fields),*,
-            #(#in_rev_fields),*
+            #(#out_fields,)*
+            #(#in_fields,)*
+            #(#rev_fields,)*
+            #(#delta_fields,)*
+            __crusp__clock: u64,
+            __crusp__marker: #marker,
+        }
+
+        #link_enum
+
+        #(#in_events_handler)*
+
+        #(#inout_events_handler)*
+
+        #graph_builder_impl
+
+        #graph_impl
+
+        #(#impl_outputs)*
+
+        #impl_backtrack
+
+        #impl_combined
+    );
+    expanded.into()
+}
+
+// The single field of a newtype, named or not.
+fn newtype_field<'a>(ast: &'a DeriveInput, derive: &str) -> syn::Result<(syn::Member, &'a syn::Type)> {
+    let message = format!("`{}` expects a struct with a single field", derive);
+    let fields = match ast.data {
+        syn::Data::Struct(syn::DataStruct { ref fields, .. }) => fields,
+        syn::Data::Enum(syn::DataEnum { enum_token, .. }) => {
+            return Err(syn::Error::new_spanned(enum_token, message));
+        }
+        syn::Data::Union(syn::DataUnion { union_token, .. }) => {
+            return Err(syn::Error::new_spanned(union_token, message));
         }
+    };
+    if fields.len() != 1 {
+        return Err(syn::Error::new(ast.ident.span(), message));
+    }
+    let field = fields.iter().next().expect("one field");
+    let member = match field.ident {
+        Some(ref ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(0.into()),
+    };
+    Ok((member, &field.ty))
+}
 
-        #(#impl_visitors)*
+// `Self { field: value }` or `Self(value)`, built by the macro so that it is not linted as user code.
+fn newtype_init(member: &syn::Member, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match member {
+        syn::Member::Named(ident) => {
+            let ident = syn::Ident::new(&ident.to_string(), span!());
+            quote!(Self { #ident: #value })
+        }
+        syn::Member::Unnamed(_) => quote!(Self(#value)),
+    }
+}
 
-       #impl_visit_all
+/// Implements `GraphNode` and the conversions from and to `usize` for a `usize` newtype.
+/// The other bounds of `GraphNode` are derived as usual.
+#[proc_macro_derive(GraphNode)]
+pub fn derive_graph_node(item: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(item as DeriveInput);
+    let (member, ty) = match newtype_field(&ast, "GraphNode") {
+        Ok(field) => field,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    // type errors are reported on the field
+    let from_usize = newtype_init(&member, quote_spanned!(ty.span()=> idx));
+    let expanded = quote!(
+        impl #impl_generics ::crusp_graph::GraphNode for #name #ty_generics #where_clause {}
 
-       #(#in_events_handler)*
+        impl #impl_generics ::std::convert::From<#name #ty_generics> for usize #where_clause
+        {
+            fn from(node: #name #ty_generics) -> Self {
+                node.#member
+            }
+        }
 
-       #(#inout_events_handler)*
+        impl #impl_generics ::std::convert::From<usize> for #name #ty_generics #where_clause
+        {
+            fn from(idx: usize) -> Self {
+                #from_usize
+            }
+        }
+    );
+    expanded.into()
+}
 
-       #graph_builder_impl
+/// Implements `GraphEvent` for a newtype of bit flags:
+/// the null event has no flag, merging is a union and
+/// `a.is_subsumed_under(&b)` holds when the flags of `b` are a subset of those of `a`.
+#[proc_macro_derive(GraphEvent)]
+pub fn derive_graph_event(item: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(item as DeriveInput);
+    let (member, ty) = match newtype_field(&ast, "GraphEvent") {
+        Ok(field) => field,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    // type errors are reported on the field
+    let null = newtype_init(&member, quote_spanned!(ty.span()=> 0));
+    let is_null = quote_spanned!(ty.span()=> self.#member == 0);
+    let merge = newtype_init(&member, quote_spanned!(ty.span()=> self.#member | rhs.#member));
+    let subsumed = quote_spanned!(ty.span()=> self.#member & rhs.#member == rhs.#member);
+    let expanded = quote!(
+        impl #impl_generics ::crusp_graph::Nullable for #name #ty_generics #where_clause
+        {
+            fn is_null(&self) -> bool {
+                #is_null
+            }
+            fn null() -> Self {
+                #null
+            }
+            fn nullify(&mut self) -> Self {
+                ::std::mem::replace(self, <Self as ::crusp_graph::Nullable>::null())
+            }
+        }
 
-       #graph_impl
+        impl #impl_generics ::crusp_graph::Mergeable for #name #ty_generics #where_clause
+        {
+            fn merge(&self, rhs: Self) -> Self {
+                #merge
+            }
+        }
 
-       #impl_pop
+        impl #impl_generics ::crusp_graph::Subsumed for #name #ty_generics #where_clause
+        {
+            fn is_subsumed_under(&self, rhs: &Self) -> bool {
+                #subsumed
+            }
+        }
 
-       #impl_pop_look
+        impl #impl_generics ::crusp_graph::GraphEvent for #name #ty_generics #where_clause {}
     );
     expanded.into()
 }
@@ -0,0 +1,101 @@
+//! Compares the compressed sparse row layout of `LazyInputEventGraph` with one vector of links
+//! per input node, on the events of a million variables.
+//!
+//! Run with `cargo bench --bench layout`.
+
+use crusp_core::{ConstraintId, Mergeable, Subsumed, VariableId};
+use crusp_graph::{FdEvent, LazyInputEventHandler};
+
+use std::time::{Duration, Instant};
+
+const VARIABLES: usize = 1_000_000;
+const LINKS: usize = 4;
+const ROUNDS: usize = 20;
+const EVENTS: [FdEvent; 5] = [FdEvent::DOMAIN, FdEvent::BOUNDS, FdEvent::LB, FdEvent::UB, FdEvent::FIX];
+
+// Small deterministic generator so both layouts see the same workload.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) as usize
+    }
+}
+
+fn links() -> Vec<(VariableId, FdEvent, ConstraintId)> {
+    let mut rng = Lcg(42);
+    (0..VARIABLES * LINKS)
+        .map(|_| {
+            let var = VariableId::from(rng.next() % VARIABLES);
+            let event = EVENTS[rng.next() % EVENTS.len()];
+            (var, event, ConstraintId::from(rng.next() % VARIABLES))
+        })
+        .collect()
+}
+
+fn notifications() -> Vec<(VariableId, FdEvent)> {
+    let mut rng = Lcg(7);
+    (0..VARIABLES)
+        .map(|_| (VariableId::from(rng.next() % VARIABLES), EVENTS[rng.next() % EVENTS.len()]))
+        .collect()
+}
+
+fn run_nested(links: &[(VariableId, FdEvent, ConstraintId)], notifications: &[(VariableId, FdEvent)]) -> (Duration, usize) {
+    let mut graph: Vec<Vec<(FdEvent, ConstraintId)>> = Vec::new();
+    graph.resize_with(VARIABLES, Vec::new);
+    for &(var, event, out) in links.iter() {
+        graph[usize::from(var)].push((event, out));
+    }
+    let mut triggered = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for &(var, in_event) in notifications.iter() {
+            for (link_event, out) in graph[usize::from(var)].iter() {
+                if in_event.is_subsumed_under(&link_event.merge(in_event)) {
+                    triggered += usize::from(*out) & 1;
+                }
+            }
+        }
+    }
+    (start.elapsed(), triggered)
+}
+
+fn run_csr(links: &[(VariableId, FdEvent, ConstraintId)], notifications: &[(VariableId, FdEvent)]) -> (Duration, usize) {
+    let mut graph = LazyInputEventHandler::builder();
+    for &(var, event, out) in links.iter() {
+        graph.add_event(var, event, out);
+    }
+    let handler = LazyInputEventHandler::new(graph.finalize());
+    let mut triggered = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for &(var, in_event) in notifications.iter() {
+            handler.process_in_event(&var, &in_event, &mut |out: &ConstraintId| {
+                triggered += usize::from(*out) & 1;
+            });
+        }
+    }
+    (start.elapsed(), triggered)
+}
+
+fn main() {
+    let links = links();
+    let notifications = notifications();
+    let (nested, triggered_nested) = run_nested(&links, &notifications);
+    let (csr, triggered_csr) = run_csr(&links, &notifications);
+    assert_eq!(triggered_nested, triggered_csr);
+    let ops = (ROUNDS * notifications.len()) as f64;
+    println!(
+        "nested vectors: {:>8.2?} ({:.1} ns/event, {} triggered)",
+        nested,
+        nested.as_nanos() as f64 / ops,
+        triggered_nested
+    );
+    println!(
+        "csr:            {:>8.2?} ({:.1} ns/event, {} triggered)",
+        csr,
+        csr.as_nanos() as f64 / ops,
+        triggered_csr
+    );
+}
@@ -0,0 +1,74 @@
+//! Compares the schedulers on a propagation-like workload: nodes of random cost classes are
+//! scheduled, some of them several times, then all of them are popped.
+//!
+//! Run with `cargo bench --bench scheduler`.
+
+use crusp_core::ConstraintId;
+use crusp_graph::{CostClass, CostClassScheduler, PriorityScheduler, Scheduler};
+
+use std::time::{Duration, Instant};
+
+const NODES: usize = 100_000;
+const ROUNDS: usize = 50;
+const CLASSES: [CostClass; CostClass::COUNT] = [
+    CostClass::Unary,
+    CostClass::Binary,
+    CostClass::Linear,
+    CostClass::Quadratic,
+    CostClass::Global,
+];
+
+// Small deterministic generator so both schedulers see the same workload.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) as usize
+    }
+}
+
+fn workload() -> Vec<(ConstraintId, i64)> {
+    let mut rng = Lcg(42);
+    (0..2 * NODES)
+        .map(|_| {
+            let node = ConstraintId::from(rng.next() % NODES);
+            let cost: i64 = CLASSES[rng.next() % CostClass::COUNT].into();
+            (node, cost)
+        })
+        .collect()
+}
+
+fn run<Sched: Scheduler<ConstraintId>>(workload: &[(ConstraintId, i64)]) -> (Duration, usize) {
+    let mut sched = Sched::default();
+    let mut popped = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for &(node, cost) in workload.iter() {
+            sched.schedule(node, cost);
+        }
+        while sched.pop().is_some() {
+            popped += 1;
+        }
+    }
+    (start.elapsed(), popped)
+}
+
+fn main() {
+    let workload = workload();
+    let (priority, popped_priority) = run::<PriorityScheduler<ConstraintId>>(&workload);
+    let (bucket, popped_bucket) = run::<CostClassScheduler<ConstraintId>>(&workload);
+    let ops = (ROUNDS * workload.len()) as f64;
+    println!(
+        "priority queue: {:>8.2?} ({:.1} ns/schedule, {} pops)",
+        priority,
+        priority.as_nanos() as f64 / ops,
+        popped_priority
+    );
+    println!(
+        "bucket queue:   {:>8.2?} ({:.1} ns/schedule, {} pops)",
+        bucket,
+        bucket.as_nanos() as f64 / ops,
+        popped_bucket
+    );
+}
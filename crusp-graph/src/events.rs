@@ -0,0 +1,154 @@
+use crate::GraphEvent;
+use crusp_core::{Mergeable, Nullable, Subsumed};
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{BitAnd, BitOr};
+
+/// Integer types usable as the bits of a `FlagEvent`.
+pub trait Flags: Copy + Eq + Hash + Debug + BitOr<Output = Self> + BitAnd<Output = Self> {
+    /// No flag set.
+    const EMPTY: Self;
+}
+
+macro_rules! impl_flags {
+    ($($int:ty),*) => {
+        $(impl Flags for $int {
+            const EMPTY: Self = 0;
+        })*
+    };
+}
+
+impl_flags!(u8, u16, u32, u64, u128, usize);
+
+/// Event made of bit flags: the null event has no flag and merging two events is their union.
+///
+/// `a.is_subsumed_under(&b)` holds when the flags of `b` are a subset of those of `a`,
+/// so an input event only triggers the links whose event flags it all carries.
+/// An event implying another one must then also carry its flags.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FlagEvent<T: Flags> {
+    bits: T,
+}
+
+impl<T: Flags> FlagEvent<T> {
+    pub fn new(bits: T) -> Self {
+        FlagEvent { bits }
+    }
+
+    pub fn bits(&self) -> T {
+        self.bits
+    }
+
+    /// True if all the flags of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl<T: Flags> Default for FlagEvent<T> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T: Flags> From<T> for FlagEvent<T> {
+    fn from(bits: T) -> Self {
+        FlagEvent::new(bits)
+    }
+}
+
+impl<T: Flags> Nullable for FlagEvent<T> {
+    fn is_null(&self) -> bool {
+        self.bits == T::EMPTY
+    }
+    fn null() -> Self {
+        FlagEvent::new(T::EMPTY)
+    }
+    fn nullify(&mut self) -> Self {
+        std::mem::take(self)
+    }
+}
+
+impl<T: Flags> Mergeable for FlagEvent<T> {
+    fn merge(&self, rhs: Self) -> Self {
+        FlagEvent::new(self.bits | rhs.bits)
+    }
+}
+
+impl<T: Flags> Subsumed for FlagEvent<T> {
+    fn is_subsumed_under(&self, rhs: &Self) -> bool {
+        self.contains(*rhs)
+    }
+}
+
+impl<T: Flags> GraphEvent for FlagEvent<T> {}
+
+/// Finite domain event of a variable, from the weakest to the strongest:
+///
+/// - `DOMAIN`: a value was removed from the domain,
+/// - `BOUNDS`: the lower or the upper bound changed,
+/// - `LB` and `UB`: the lower (resp. upper) bound changed,
+/// - `FIX`: the variable is instantiated.
+///
+/// Each event carries the flags of the events it implies, so `FIX` implies both bounds,
+/// the bounds imply `BOUNDS` and `BOUNDS` implies `DOMAIN`.
+/// Merging is the union of the flags: `LB` merged with `UB` changes both bounds but is not `FIX`.
+/// The flags, their merge and their subsumption are those of `FlagEvent<u8>`.
+///
+/// `LazyInputEventHandler::process_in_event` triggers a link registered with an event `E`
+/// on an input event `N` when `N` implies `E`: a propagator subscribed to `BOUNDS` is
+/// triggered by `LB`, `UB`, `BOUNDS` and `FIX` but not by the removal of an inner value (`DOMAIN`).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct FdEvent {
+    flags: FlagEvent<u8>,
+}
+
+impl FdEvent {
+    pub const DOMAIN: FdEvent = FdEvent::from_bits(0b00001);
+    pub const BOUNDS: FdEvent = FdEvent::from_bits(0b00011);
+    pub const LB: FdEvent = FdEvent::from_bits(0b00111);
+    pub const UB: FdEvent = FdEvent::from_bits(0b01011);
+    pub const FIX: FdEvent = FdEvent::from_bits(0b11111);
+
+    const fn from_bits(bits: u8) -> Self {
+        FdEvent {
+            flags: FlagEvent { bits },
+        }
+    }
+
+    /// True if this event implies `other`.
+    pub fn implies(&self, other: Self) -> bool {
+        self.flags.contains(other.flags)
+    }
+}
+
+impl Nullable for FdEvent {
+    fn is_null(&self) -> bool {
+        self.flags.is_null()
+    }
+    fn null() -> Self {
+        FdEvent {
+            flags: FlagEvent::null(),
+        }
+    }
+    fn nullify(&mut self) -> Self {
+        std::mem::take(self)
+    }
+}
+
+impl Mergeable for FdEvent {
+    fn merge(&self, rhs: Self) -> Self {
+        FdEvent {
+            flags: self.flags.merge(rhs.flags),
+        }
+    }
+}
+
+impl Subsumed for FdEvent {
+    fn is_subsumed_under(&self, rhs: &Self) -> bool {
+        self.flags.is_subsumed_under(&rhs.flags)
+    }
+}
+
+impl GraphEvent for FdEvent {}
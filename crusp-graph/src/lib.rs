@@ -21,23 +21,30 @@
         clippy::cast_possible_wrap, clippy::cast_possible_truncation,
         clippy::mutex_integer, clippy::mut_mut, clippy::items_after_statements,
         clippy::print_stdout, clippy::mem_forget, clippy::maybe_infinite_iter)]
-
-use crusp_core::{Mergeable, Nullable, Subsumed};
-use priority_queue::PriorityQueue;
+#![forbid(unsafe_code)]
 
 use std::{default::Default, marker::PhantomData};
 use std::fmt::Debug;
 use std::rc::Rc;
 
+mod events;
+mod scheduler;
+mod scoring;
+mod stats;
+
+// the event traits, so that users of the derives do not depend on `crusp-core` themselves
+pub use crusp_core::{Mergeable, Nullable, Subsumed};
+pub use crate::scheduler::{
+    CostClass, CostClassScheduler, FifoScheduler, LifoScheduler, MultiLevelScheduler, PriorityScheduler, Scheduler,
+};
+pub use crate::events::{FdEvent, FlagEvent, Flags};
+pub use crate::scoring::{ActivityScores, ChbScores};
+pub use crate::stats::{Distribution, GraphStats, InputStats};
+
 // TODO MAYBE SPLIT EVENT HANDLER AND GRAPH CONSTRAINT LIST OF VARIABLES
 
-// TODO(vincent): variables: active failure count: almost ok
 // TODO(vincent): variables: actions: almost ok
-// TODO(vincent): chb read more: ?????
 
-// TODO(vincent): variables: last change
-// TODO(vincent): disable constraints
-// TODO(vincent): add constraint
 // TODO(vincent): Add builder then create proc macro for graph auto generation
 // TODO(vincent): rmv useless pub
 
@@ -72,10 +79,29 @@ where
 {
     /// Notify incoming event to the handler. Do not necessarly trigger the event.
     fn notify(&mut self, node: &InNode, event: &InEvent) -> bool;
-    // Tells if any non null event occurs  for the node `node` since the last call to peek_change
-    //fn peek_change(&mut self, node: &InNode) -> bool;
 }
 
+/// Timestamps increase with each notified non null event.
+pub trait Timestamped {
+    /// The timestamp of the last notified event.
+    fn timestamp(&self) -> u64;
+}
+
+pub trait InputChangeTracker<InNode>
+where
+    InNode: GraphNode,
+{
+    /// The timestamp of the last non null event notified for `node`, 0 if none.
+    fn last_change(&self, node: &InNode) -> u64;
+    fn changed_since(&self, node: &InNode, time: u64) -> bool {
+        self.last_change(node) > time
+    }
+    /// Tells if any non null event occurs for the node `node` since the last call to peek_change.
+    fn peek_change(&mut self, node: &InNode) -> bool;
+}
+
+/// Links an input event to an output event.
+/// Also implemented by finalized graphs to insert new links during search.
 pub trait InOutEventHandlerBuilder<OutNode, OutEvent, InNode, InEvent>
 where
     OutNode: GraphNode,
@@ -106,6 +132,23 @@ where
     fn collect_not_ignored(&mut self) {
         self.collect(None)
     }
+    /// Same as `collect_and_pop` but ignores the events of all the given output nodes,
+    /// e.g. for propagators that run as a group.
+    fn collect_and_pop_ignoring(&mut self, ignored: &[OutNode]) -> Option<(OutNode, OutEvent)>;
+    fn collect_ignoring(&mut self, ignored: &[OutNode]);
+}
+
+/// Delivers with each popped output node the input events that woke it up since it was last popped.
+/// Only the events collected by `collect_delta_and_pop` are delivered.
+pub trait OutputDeltaEventHandler<OutNode, OutEvent, Visitor>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+{
+    fn collect_delta_and_pop_not_ignored(&mut self, visitor: &mut Visitor) -> Option<(OutNode, OutEvent)> {
+        self.collect_delta_and_pop(None, visitor)
+    }
+    fn collect_delta_and_pop(&mut self, ignored: Option<OutNode>, visitor: &mut Visitor) -> Option<(OutNode, OutEvent)>;
 }
 
 pub trait OutputEventHandlerLookup<OutNode, OutEvent, Look>
@@ -125,14 +168,113 @@ where
     }
 }
 
+/// Disabled output nodes are neither collected nor popped until they are enabled again.
+pub trait DisableOutputNode<OutNode>
+where
+    OutNode: GraphNode,
+{
+    fn disable(&mut self, out_node: &OutNode);
+    fn enable(&mut self, out_node: &OutNode);
+    fn is_disabled(&self, out_node: &OutNode) -> bool;
+}
+
+/// Pops across the outputs of a graph with several outputs, `Output` tells which output was popped.
+pub trait CombinedOutputEventHandler<Output> {
+    /// Collects the events of all the outputs.
+    fn collect_all(&mut self);
+    /// Pops from the output with the lowest rank, then with the highest cost,
+    /// then from the first declared one.
+    fn pop_any(&mut self) -> Option<Output>;
+    fn collect_and_pop_any(&mut self) -> Option<Output> {
+        self.collect_all();
+        self.pop_any()
+    }
+}
+
+/// The events of an idempotent output node are not collected while it is running,
+/// i.e. from the moment it is popped until the next node is popped.
+pub trait IdempotentOutputNode<OutNode>
+where
+    OutNode: GraphNode,
+{
+    fn set_idempotent(&mut self, out_node: &OutNode, idempotent: bool);
+    fn is_idempotent(&self, out_node: &OutNode) -> bool;
+}
+
+/// Saves the current state with `push_level` and restores it with `pop_level`.
+/// Restoring a level only undoes the changes made since it was pushed.
+pub trait Backtrackable {
+    fn push_level(&mut self);
+    fn pop_level(&mut self);
+}
+
+/// Counts the failures of the nodes, e.g. for dom/wdeg heuristics.
+pub trait FailureHandler<Node>
+where
+    Node: GraphNode,
+{
+    fn notify_failure(&mut self, node: &Node);
+}
+
+/// Weighted degree of the input nodes: the failures of the output nodes linked to them.
+pub trait WeightedDegree<Node>
+where
+    Node: GraphNode,
+{
+    fn weighted_degree(&self, node: &Node) -> u64;
+}
+
+/// Weight of the output nodes, i.e. their number of failures.
+/// Kept apart from `WeightedDegree` so that the input and output node types of a graph may unify.
+pub trait OutputWeight<OutNode>
+where
+    OutNode: GraphNode,
+{
+    fn weight(&self, out_node: &OutNode) -> u64;
+}
+
 pub trait VisitMut<T> {
     fn visit_mut(&mut self, t: &T);
 }
 
+/// Forwards to `visitor` the visited nodes that changed after `since`.
+pub struct ChangedVisitor<'a, Tracker, Visitor> {
+    pub tracker: &'a Tracker,
+    pub since: u64,
+    pub visitor: &'a mut Visitor,
+}
+
+impl<'a, Node, Tracker, Visitor> VisitMut<Node> for ChangedVisitor<'a, Tracker, Visitor>
+where
+    Node: GraphNode,
+    Tracker: InputChangeTracker<Node>,
+    Visitor: VisitMut<Node>,
+{
+    fn visit_mut(&mut self, node: &Node) {
+        if self.tracker.changed_since(node, self.since) {
+            self.visitor.visit_mut(node);
+        }
+    }
+}
+
+/// Notifies a failure to the handler for each visited node.
+pub struct FailureVisitor<'a, Handler>(pub &'a mut Handler);
+
+impl<'a, Node, Handler> VisitMut<Node> for FailureVisitor<'a, Handler>
+where
+    Node: GraphNode,
+    Handler: FailureHandler<Node>,
+{
+    fn visit_mut(&mut self, node: &Node) {
+        self.0.notify_failure(node);
+    }
+}
+
 pub trait LookEvent<Node: GraphNode, Event: GraphEvent> {
     fn look_event(&mut self, node: &Node, event: &Event);
 }
 
+/// Also implemented by finalized graphs to insert new nodes during search.
 pub trait GraphBuilder<OutNode, InNode>
 where
     OutNode: GraphNode,
@@ -151,6 +293,15 @@ where
         Visitor: VisitMut<InNode>;
 }
 
+/// Iterator over the input nodes of an output node, in increasing order.
+pub trait InNodes<OutNode, InNode>
+where
+    OutNode: GraphNode,
+    InNode: GraphNode,
+{
+    fn in_nodes(&self, out_node: &OutNode) -> InNodesIter<'_, InNode>;
+}
+
 pub trait VisitAllOutputsNode<OutNode, Visitor>
 where
     OutNode: GraphNode,
@@ -158,26 +309,91 @@ where
     fn visit_all_in_nodes(&self, out_node: &OutNode, visitor: &mut Visitor);
 }
 
+/// Visits the input nodes of `out_node` that changed after `since`.
+pub trait VisitChangedOutputsNode<OutNode, InNode>
+where
+    OutNode: GraphNode,
+    InNode: GraphNode,
+{
+    fn visit_changed_in_nodes<Visitor>(&self, out_node: &OutNode, since: u64, visitor: &mut Visitor)
+    where
+        Visitor: VisitMut<InNode>;
+}
+
+pub trait VisitAllChangedOutputsNode<OutNode, Visitor>
+where
+    OutNode: GraphNode,
+{
+    fn visit_all_changed_in_nodes(&self, out_node: &OutNode, since: u64, visitor: &mut Visitor);
+}
+
+/// Output nodes subscribed to an input node, as `(out_node, in_event, out_event, cost)`
+/// where `in_event` is the event of the link, i.e. the mask matched against the input events.
+pub trait Subscribers<InNode, InEvent, OutNode, OutEvent>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+{
+    /// Links of the input nodes, possibly to several outputs.
+    type Link: OutputLink<OutNode, OutEvent>;
+
+    fn subscribers(&self, in_node: &InNode) -> SubscribersIter<'_, InEvent, Self::Link, OutNode, OutEvent>;
+
+    /// The subscribers triggered by `in_event`.
+    fn triggered_subscribers(
+        &self,
+        in_node: &InNode,
+        in_event: &InEvent,
+    ) -> SubscribersIter<'_, InEvent, Self::Link, OutNode, OutEvent> {
+        self.subscribers(in_node).triggered_by(*in_event)
+    }
+}
+
+/// Link of an input node to an output node, `None` if it links to another output.
+pub trait OutputLink<OutNode, OutEvent>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+{
+    fn out_link(&self) -> Option<&OutCostEventLink<OutNode, OutEvent>>;
+}
+
+#[derive(Clone)]
 struct EventLink<InEvent: GraphEvent, Output> {
     in_event: InEvent,
     out: Output,
 }
 
+// A link is triggered if `in_event` implies its event.
+#[inline]
+fn triggers<InEvent: GraphEvent>(link_event: &InEvent, in_event: &InEvent) -> bool {
+    in_event.is_subsumed_under(&link_event.merge(*in_event))
+}
+
 pub struct LazyInputEventGraphBuilder<InNode, InEvent, Output>
 where
     InNode: GraphNode,
     InEvent: GraphEvent,
 {
-    in_events: Vec<Vec<EventLink<InEvent, Output>>>,
+    links: Vec<(usize, EventLink<InEvent, Output>)>,
     _in_node: PhantomData<InNode>,
 }
 
+/// Links of the input nodes in compressed sparse row layout: the links of the node `idx` are
+/// at `offsets[idx]..offsets[idx + 1]` of `in_events` and `outs`.
+/// Links inserted once finalized are kept aside and come after the other links of their node.
+#[derive(Clone)]
 pub struct LazyInputEventGraph<InNode, InEvent, Output>
 where
     InNode: GraphNode,
     InEvent: GraphEvent,
 {
-    in_events: Vec<Vec<EventLink<InEvent, Output>>>,
+    offsets: Vec<usize>,
+    in_events: Vec<InEvent>,
+    outs: Vec<Output>,
+    inserted: Vec<Vec<EventLink<InEvent, Output>>>,
     _in_node: PhantomData<InNode>,
 }
 
@@ -198,27 +414,113 @@ where
 {
     pub fn new() -> Self {
         LazyInputEventGraphBuilder {
-            in_events: Vec::new(),
+            links: Vec::new(),
             _in_node: PhantomData,
         }
     }
 
-    #[allow(clippy::shadow_reuse)]
+    pub fn add_event(&mut self, node: InNode, event: InEvent, out: Output) {
+        self.links.push((node.into(), EventLink { in_event: event, out }));
+    }
+
+    pub fn finalize(mut self) -> LazyInputEventGraph<InNode, InEvent, Output> {
+        // stable: the links of a node keep their insertion order
+        self.links.sort_by_key(|&(idx, _)| idx);
+        let len = self.links.last().map_or(0, |&(idx, _)| idx + 1);
+        let mut offsets = vec![0; len + 1];
+        for &(idx, _) in self.links.iter() {
+            offsets[idx + 1] += 1;
+        }
+        for idx in 0..len {
+            offsets[idx + 1] += offsets[idx];
+        }
+        let mut in_events = Vec::with_capacity(self.links.len());
+        let mut outs = Vec::with_capacity(self.links.len());
+        for (_, link) in self.links {
+            in_events.push(link.in_event);
+            outs.push(link.out);
+        }
+        LazyInputEventGraph {
+            offsets,
+            in_events,
+            outs,
+            inserted: Vec::new(),
+            _in_node: PhantomData,
+        }
+    }
+}
+
+impl<InNode, InEvent, Output> LazyInputEventGraph<InNode, InEvent, Output>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
     pub fn add_event(&mut self, node: InNode, event: InEvent, out: Output) {
         let idx: usize = node.into();
-        if idx >= self.in_events.len() {
-            self.in_events.resize_with(idx + 1, Vec::new)
+        if idx >= self.inserted.len() {
+            self.inserted.resize_with(idx + 1, Vec::new)
         }
-        self.in_events[idx].push(EventLink {
-            in_event: event,
-            out: out,
+        self.inserted[idx].push(EventLink { in_event: event, out });
+    }
+
+    /// Links of `in_node` with their events, in insertion order.
+    pub fn links(&self, in_node: &InNode) -> Links<'_, InEvent, Output> {
+        let in_idx: usize = (*in_node).into();
+        let links = if in_idx + 1 < self.offsets.len() {
+            self.offsets[in_idx]..self.offsets[in_idx + 1]
+        } else {
+            0..0
+        };
+        let inserted = self.inserted.get(in_idx).map_or(&[][..], |inserted| &inserted[..]);
+        Links {
+            in_events: self.in_events[links.clone()].iter(),
+            outs: self.outs[links].iter(),
+            inserted: inserted.iter(),
+        }
+    }
+
+    pub fn stats(&self) -> InputStats {
+        let len = self.inserted.len().max(self.offsets.len().saturating_sub(1));
+        let nodes = || (0..len).map(InNode::from);
+        let event_masks = nodes().map(|in_node| {
+            let mut masks: Vec<InEvent> = Vec::new();
+            for (link_event, _) in self.links(&in_node) {
+                if !masks
+                    .iter()
+                    .any(|mask| mask.is_subsumed_under(link_event) && link_event.is_subsumed_under(mask))
+                {
+                    masks.push(*link_event);
+                }
+            }
+            masks.len()
         });
+        InputStats {
+            degrees: Distribution::from_counts(nodes().map(|in_node| self.links(&in_node).count())),
+            event_masks: Distribution::from_counts(event_masks),
+        }
     }
 
-    pub fn finalize(self) -> LazyInputEventGraph<InNode, InEvent, Output> {
-        LazyInputEventGraph {
-            in_events: self.in_events,
-            _in_node: PhantomData,
+    /// Visits the outputs of the links of `in_node` triggered by `in_event`.
+    #[inline]
+    fn process<F>(&self, in_node: &InNode, in_event: &InEvent, process: &mut F)
+    where
+        F: FnMut(&Output),
+    {
+        let in_idx: usize = (*in_node).into();
+        if in_idx + 1 < self.offsets.len() {
+            let links = self.offsets[in_idx]..self.offsets[in_idx + 1];
+            for (link_event, out) in self.in_events[links.clone()].iter().zip(self.outs[links].iter()) {
+                if triggers(link_event, in_event) {
+                    process(out);
+                }
+            }
+        }
+        if let Some(inserted) = self.inserted.get(in_idx) {
+            for link in inserted.iter() {
+                if triggers(&link.in_event, in_event) {
+                    process(&link.out);
+                }
+            }
         }
     }
 }
@@ -230,7 +532,12 @@ where
 {
     graph: Rc<LazyInputEventGraph<InNode, InEvent, Output>>,
     events: Vec<(InNode, InEvent)>,
-    //    changes: HahshMap<InNode, bool>,
+    // pending events of each level
+    levels: Vec<Vec<(InNode, InEvent)>>,
+    weights: Vec<u64>,
+    clock: u64,
+    changes: Vec<u64>,
+    peeks: Vec<u64>,
 }
 
 impl<InNode, InEvent, Output> InputEventHandler<InNode, InEvent>
@@ -240,31 +547,40 @@ where
     InEvent: GraphEvent,
 {
     fn notify(&mut self, node: &InNode, event: &InEvent) -> bool {
-        if event.is_null() {
-            return false;
-        }
-        match self.events.last_mut() {
-            Some(&mut (l_node, ref mut l_evt)) if l_node.into() == (*node).into() => {
-                *l_evt = l_evt.merge(*event);
-            }
-            _ => {
-                self.events.push((*node, *event));
-            }
-        }
-        true
+        let time = self.clock + 1;
+        self.notify_at(node, event, time)
     }
+}
 
-    /*fn peek_change(&mut self, node: &InNode) -> bool {
-        unimplemented!()
-        match self.changes.get_mut(node) {
-            Some(ref mut ch) => {
-                let ret = *ch;
-                *ch = true;
-                ret
-            },
-            None => false
+impl<InNode, InEvent, Output> Timestamped for LazyInputEventHandler<InNode, InEvent, Output>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    fn timestamp(&self) -> u64 {
+        self.clock
+    }
+}
+
+impl<InNode, InEvent, Output> InputChangeTracker<InNode>
+    for LazyInputEventHandler<InNode, InEvent, Output>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    fn last_change(&self, node: &InNode) -> u64 {
+        self.changes.get((*node).into()).copied().unwrap_or(0)
+    }
+
+    fn peek_change(&mut self, node: &InNode) -> bool {
+        let idx: usize = (*node).into();
+        if idx >= self.peeks.len() {
+            self.peeks.resize(idx + 1, 0);
         }
-    }*/
+        let changed = self.last_change(node) > self.peeks[idx];
+        self.peeks[idx] = self.clock;
+        changed
+    }
 }
 
 impl<InNode, InEvent, Output> LazyInputEventHandler<InNode, InEvent, Output>
@@ -280,12 +596,57 @@ where
         LazyInputEventHandler {
             graph: Rc::new(graph),
             events: Vec::new(),
+            levels: Vec::new(),
+            weights: Vec::new(),
+            clock: 0,
+            changes: Vec::new(),
+            peeks: Vec::new(),
         }
     }
 
+    /// Notifies the event with an externally managed timestamp, e.g. one shared between handlers.
+    /// `time` must be greater than the timestamps of the previous events.
+    pub fn notify_at(&mut self, node: &InNode, event: &InEvent, time: u64) -> bool {
+        if event.is_null() {
+            return false;
+        }
+        let idx: usize = (*node).into();
+        if idx >= self.changes.len() {
+            self.changes.resize(idx + 1, 0);
+        }
+        self.changes[idx] = time;
+        self.clock = time;
+        match self.events.last_mut() {
+            Some(&mut (l_node, ref mut l_evt)) if l_node.into() == (*node).into() => {
+                *l_evt = l_evt.merge(*event);
+            }
+            _ => {
+                self.events.push((*node, *event));
+            }
+        }
+        true
+    }
+
+    /// Inserts a new link in the graph, seen by the next call to `trigger_events`.
+    /// The graph is cloned first if it is shared.
+    pub fn add_event(&mut self, node: InNode, event: InEvent, out: Output)
+    where
+        Output: Clone,
+    {
+        Rc::make_mut(&mut self.graph).add_event(node, event, out);
+    }
+
     pub fn trigger_events<F>(&mut self, mut process: F)
     where
         F: FnMut(&Output),
+    {
+        self.trigger_delta_events(|_, _, out| process(out));
+    }
+
+    /// Same as `trigger_events` but also gives the input event that triggered each output.
+    pub fn trigger_delta_events<F>(&mut self, mut process: F)
+    where
+        F: FnMut(&InNode, &InEvent, &Output),
     {
         if self.events.is_empty() {
             return;
@@ -301,12 +662,12 @@ where
             if curr_node.into() == in_node.into() {
                 curr_event = curr_event.merge(in_event);
             } else {
-                self.process_in_event(&curr_node, &curr_event, &mut process);
+                self.process_in_event(&curr_node, &curr_event, &mut |out| process(&curr_node, &curr_event, out));
                 curr_node = in_node;
                 curr_event = in_event;
             }
         }
-        self.process_in_event(&curr_node, &curr_event, &mut process);
+        self.process_in_event(&curr_node, &curr_event, &mut |out| process(&curr_node, &curr_event, out));
     }
 
     pub fn trigger_look_events<F, Look>(&mut self, mut process: F, look_in: &mut Look)
@@ -338,19 +699,156 @@ where
         self.process_in_event(&curr_node, &curr_event, &mut process);
     }
 
-    #[allow(clippy::filter_map)]
+    /// Links of `in_node` with their events, in insertion order.
+    pub fn links(&self, in_node: &InNode) -> Links<'_, InEvent, Output> {
+        self.graph.links(in_node)
+    }
+
+    pub fn stats(&self) -> InputStats {
+        self.graph.stats()
+    }
+
     pub fn process_in_event<F>(&self, in_node: &InNode, in_event: &InEvent, process: &mut F)
     where
         F: FnMut(&Output),
     {
-        // /self.changes.entry(in_node).or_insert(true);
-        // TODO: rmv bound checks
-        let in_idx: usize = (*in_node).into();
-        self.graph.in_events[in_idx]
-            .iter()
-            .filter(|&out_event| in_event.is_subsumed_under(&out_event.in_event.merge(*in_event)))
-            .map(|link| &link.out)
-            .for_each(|out| process(out));
+        self.graph.process(in_node, in_event, process);
+    }
+}
+
+impl<InNode, InEvent, Output> Backtrackable for LazyInputEventHandler<InNode, InEvent, Output>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    // Pending events are usually empty at a choice point, so saving them is cheap.
+    fn push_level(&mut self) {
+        self.levels.push(self.events.clone());
+    }
+
+    fn pop_level(&mut self) {
+        if let Some(events) = self.levels.pop() {
+            self.events = events;
+        }
+    }
+}
+
+impl<InNode, InEvent, Output> FailureHandler<InNode> for LazyInputEventHandler<InNode, InEvent, Output>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    fn notify_failure(&mut self, node: &InNode) {
+        let idx: usize = (*node).into();
+        if idx >= self.weights.len() {
+            self.weights.resize(idx + 1, 0);
+        }
+        self.weights[idx] += 1;
+    }
+}
+
+impl<InNode, InEvent, Output> WeightedDegree<InNode> for LazyInputEventHandler<InNode, InEvent, Output>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    fn weighted_degree(&self, node: &InNode) -> u64 {
+        self.weights.get((*node).into()).copied().unwrap_or(0)
+    }
+}
+
+impl<InNode, InEvent, OutNode, OutEvent, Link> Subscribers<InNode, InEvent, OutNode, OutEvent>
+    for LazyInputEventHandler<InNode, InEvent, Link>
+where
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Link: OutputLink<OutNode, OutEvent>,
+{
+    type Link = Link;
+
+    fn subscribers(&self, in_node: &InNode) -> SubscribersIter<'_, InEvent, Link, OutNode, OutEvent> {
+        SubscribersIter::new(self.links(in_node))
+    }
+}
+
+/// Iterator over the links of an input node with their events, see `LazyInputEventGraph::links`.
+#[derive(Clone)]
+pub struct Links<'a, InEvent: GraphEvent, Output> {
+    in_events: std::slice::Iter<'a, InEvent>,
+    outs: std::slice::Iter<'a, Output>,
+    inserted: std::slice::Iter<'a, EventLink<InEvent, Output>>,
+}
+
+impl<'a, InEvent: GraphEvent, Output> Iterator for Links<'a, InEvent, Output> {
+    type Item = (&'a InEvent, &'a Output);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.in_events.next(), self.outs.next()) {
+            (Some(in_event), Some(out)) => Some((in_event, out)),
+            _ => self.inserted.next().map(|link| (&link.in_event, &link.out)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.outs.len() + self.inserted.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, InEvent: GraphEvent, Output> ExactSizeIterator for Links<'a, InEvent, Output> {}
+
+/// Iterator over the subscribers of an input node to one output, see `Subscribers`.
+pub struct SubscribersIter<'a, InEvent: GraphEvent, Link, OutNode, OutEvent> {
+    links: Links<'a, InEvent, Link>,
+    // only the links triggered by this event if any
+    event: Option<InEvent>,
+    _out: PhantomData<(OutNode, OutEvent)>,
+}
+
+impl<'a, InEvent, Link, OutNode, OutEvent> SubscribersIter<'a, InEvent, Link, OutNode, OutEvent>
+where
+    InEvent: GraphEvent,
+    Link: OutputLink<OutNode, OutEvent>,
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+{
+    pub fn new(links: Links<'a, InEvent, Link>) -> Self {
+        SubscribersIter {
+            links,
+            event: None,
+            _out: PhantomData,
+        }
+    }
+
+    /// Only keeps the subscribers triggered by `in_event`.
+    pub fn triggered_by(self, in_event: InEvent) -> Self {
+        SubscribersIter {
+            event: Some(in_event),
+            ..self
+        }
+    }
+}
+
+impl<'a, InEvent, Link, OutNode, OutEvent> Iterator for SubscribersIter<'a, InEvent, Link, OutNode, OutEvent>
+where
+    InEvent: GraphEvent,
+    Link: OutputLink<OutNode, OutEvent>,
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+{
+    type Item = (OutNode, InEvent, OutEvent, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.event;
+        self.links.find_map(|(link_event, link)| {
+            let out = link.out_link()?;
+            match event {
+                Some(ref in_event) if !triggers(link_event, in_event) => None,
+                _ => Some((out.node(), *link_event, out.event(), out.cost())),
+            }
+        })
     }
 }
 
@@ -359,8 +857,7 @@ where
     SrcNode: GraphNode,
     DstNode: GraphNode,
 {
-    len: usize,
-    ins: Vec<Vec<DstNode>>,
+    links: Vec<(usize, DstNode)>,
     _src_node: PhantomData<SrcNode>
 }
 
@@ -371,19 +868,26 @@ where
 {
     fn new() -> Self {
         AdjacentListGraphBuilder {
-            len: 0usize,
-            ins: Vec::new(),
+            links: Vec::new(),
             _src_node: PhantomData,
         }
     }
 
     pub fn finalize(mut self) -> AdjacentListGraph<SrcNode, DstNode> {
-        for ins in self.ins.iter_mut() {
-            ins[..].sort();
-            ins.dedup();
+        self.links.sort_unstable();
+        self.links.dedup();
+        let len = self.links.last().map_or(0, |&(idx, _)| idx + 1);
+        let mut offsets = vec![0; len + 1];
+        for &(idx, _) in self.links.iter() {
+            offsets[idx + 1] += 1;
+        }
+        for idx in 0..len {
+            offsets[idx + 1] += offsets[idx];
         }
         AdjacentListGraph {
-            ins: self.ins,
+            offsets,
+            ins: self.links.into_iter().map(|(_, in_node)| in_node).collect(),
+            inserted: Vec::new(),
             _src_node: PhantomData,
         }
     }
@@ -395,21 +899,22 @@ where
     InNode: GraphNode,
 {
     fn add_node(&mut self, out_node: &OutNode, in_node: &InNode) {
-        let idx: usize = (*out_node).into();
-        if idx >= self.len {
-            self.ins.resize_with(idx + 1, Vec::new);
-            self.len = idx + 1;
-        }
-        self.ins[idx].push(*in_node);
+        self.links.push(((*out_node).into(), *in_node));
     }
 }
 
+/// Sorted and deduplicated destinations of each source node in compressed sparse row layout:
+/// the destinations of the node `idx` are `ins[offsets[idx]..offsets[idx + 1]]`.
+/// Destinations inserted once finalized are kept aside, sorted, and merged when iterated.
+#[derive(Clone)]
 pub struct AdjacentListGraph<SrcNode, DstNode>
 where
     SrcNode: GraphNode,
     DstNode: GraphNode,
 {
-    ins: Vec<Vec<DstNode>>,
+    offsets: Vec<usize>,
+    ins: Vec<DstNode>,
+    inserted: Vec<Vec<DstNode>>,
     _src_node: PhantomData<SrcNode>
 }
 
@@ -421,36 +926,135 @@ where
     pub fn builder() -> AdjacentListGraphBuilder<SrcNode, DstNode> {
         AdjacentListGraphBuilder::new()
     }
+
+    /// Number of destinations of `out_node`.
+    pub fn degree(&self, out_node: &SrcNode) -> usize {
+        self.ins_of(out_node).len() + self.inserted_of(out_node).len()
+    }
+
+    /// Number of source nodes, i.e. one more than the highest source node with destinations.
+    pub fn sources(&self) -> usize {
+        self.inserted.len().max(self.offsets.len().saturating_sub(1))
+    }
+
+    /// Distribution of the degrees of the source nodes.
+    pub fn stats(&self) -> Distribution {
+        Distribution::from_counts((0..self.sources()).map(|idx| self.degree(&SrcNode::from(idx))))
+    }
+
+    /// Destinations of `out_node`, in increasing order.
+    pub fn in_nodes(&self, out_node: &SrcNode) -> InNodesIter<'_, DstNode> {
+        InNodesIter {
+            ins: self.ins_of(out_node),
+            inserted: self.inserted_of(out_node),
+        }
+    }
+
+    fn ins_of(&self, out_node: &SrcNode) -> &[DstNode] {
+        let idx: usize = (*out_node).into();
+        if idx + 1 < self.offsets.len() {
+            &self.ins[self.offsets[idx]..self.offsets[idx + 1]]
+        } else {
+            &[]
+        }
+    }
+
+    fn inserted_of(&self, out_node: &SrcNode) -> &[DstNode] {
+        self.inserted.get((*out_node).into()).map_or(&[], |inserted| &inserted[..])
+    }
 }
 
-impl<SrcNode, DstNode> VisitOutputsNode<SrcNode, DstNode> for AdjacentListGraph<SrcNode, DstNode>
+impl<SrcNode, DstNode> InNodes<SrcNode, DstNode> for AdjacentListGraph<SrcNode, DstNode>
 where
     SrcNode: GraphNode,
     DstNode: GraphNode,
 {
-    /*fn visit_all_in_nodes<Visitor>(&self, visitor: &mut Visitor)
-        where Visitor: VisitMut<DstNode>
-    {
-        if let Some(idx) = self.map_to_idx.get(out_node) {
-            let ins = self.ins[*idx].iter();
-            for v in ins {
-                visitor.visit_mut(&v);
-            }
+    fn in_nodes(&self, out_node: &SrcNode) -> InNodesIter<'_, DstNode> {
+        AdjacentListGraph::in_nodes(self, out_node)
+    }
+}
+
+impl<OutNode, InNode> GraphBuilder<OutNode, InNode> for AdjacentListGraph<OutNode, InNode>
+where
+    OutNode: GraphNode,
+    InNode: GraphNode,
+{
+    /// Only shifts the destinations inserted into `out_node` once finalized.
+    fn add_node(&mut self, out_node: &OutNode, in_node: &InNode) {
+        if self.ins_of(out_node).binary_search(in_node).is_ok() {
+            return;
+        }
+        let idx: usize = (*out_node).into();
+        if idx >= self.inserted.len() {
+            self.inserted.resize_with(idx + 1, Vec::new);
         }
-    }*/
+        let inserted = &mut self.inserted[idx];
+        if let Err(pos) = inserted.binary_search(in_node) {
+            inserted.insert(pos, *in_node);
+        }
+    }
+}
 
+impl<SrcNode, DstNode> VisitOutputsNode<SrcNode, DstNode> for AdjacentListGraph<SrcNode, DstNode>
+where
+    SrcNode: GraphNode,
+    DstNode: GraphNode,
+{
     fn visit_in_nodes<Visitor>(&self, out_node: &SrcNode, visitor: &mut Visitor)
     where
         Visitor: VisitMut<DstNode>,
     {
-        if let Some(ins) = self.ins.get((*out_node).into()) {
-            for v in ins.iter() {
-                visitor.visit_mut(&v);
-            }
+        for v in self.in_nodes(out_node) {
+            visitor.visit_mut(v);
         }
     }
 }
 
+/// Iterator over the destinations of a source node in increasing order,
+/// merging the ones of the finalized graph with the ones inserted since.
+#[derive(Clone, Debug)]
+pub struct InNodesIter<'a, Node> {
+    ins: &'a [Node],
+    inserted: &'a [Node],
+}
+
+impl<'a, Node: Ord> Iterator for InNodesIter<'a, Node> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        // both slices are sorted and disjoint
+        let from_ins = match (self.ins.first(), self.inserted.first()) {
+            (Some(node), Some(inserted)) => node < inserted,
+            (ins, _) => ins.is_some(),
+        };
+        let nodes = if from_ins { &mut self.ins } else { &mut self.inserted };
+        let (node, rest) = nodes.split_first()?;
+        *nodes = rest;
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.ins.len() + self.inserted.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, Node: Ord> DoubleEndedIterator for InNodesIter<'a, Node> {
+    fn next_back(&mut self) -> Option<&'a Node> {
+        let from_ins = match (self.ins.last(), self.inserted.last()) {
+            (Some(node), Some(inserted)) => node > inserted,
+            (ins, _) => ins.is_some(),
+        };
+        let nodes = if from_ins { &mut self.ins } else { &mut self.inserted };
+        let (node, rest) = nodes.split_last()?;
+        *nodes = rest;
+        Some(node)
+    }
+}
+
+impl<'a, Node: Ord> ExactSizeIterator for InNodesIter<'a, Node> {}
+
+#[derive(Clone)]
 pub struct OutCostEventLink<OutNode: GraphNode, OutEvent: GraphEvent> {
     idx: OutNode,
     event: OutEvent,
@@ -465,38 +1069,168 @@ impl<OutNode: GraphNode, OutEvent: GraphEvent> OutCostEventLink<OutNode, OutEven
             cost: cost,
         }
     }
+
+    pub fn node(&self) -> OutNode {
+        self.idx
+    }
+
+    pub fn event(&self) -> OutEvent {
+        self.event
+    }
+
+    pub fn cost(&self) -> i64 {
+        self.cost
+    }
+}
+
+impl<OutNode: GraphNode, OutEvent: GraphEvent> OutputLink<OutNode, OutEvent> for OutCostEventLink<OutNode, OutEvent> {
+    fn out_link(&self) -> Option<&OutCostEventLink<OutNode, OutEvent>> {
+        Some(self)
+    }
 }
 
-pub struct HandlerOutputBuilder<OutNode, OutEvent>
+/// Input events received by the output nodes since their deltas were last visited.
+/// The deltas of an output node must be cleared whenever it is popped, with or without them.
+pub struct DeltaStore<OutNode, InNode, InEvent>
+where
+    OutNode: GraphNode,
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    deltas: Vec<Vec<(InNode, InEvent)>>,
+    // output nodes that recorded deltas since the last `clear_all`, each listed once
+    dirty: Vec<OutNode>,
+    listed: Vec<bool>,
+}
+
+impl<OutNode, InNode, InEvent> Default for DeltaStore<OutNode, InNode, InEvent>
+where
+    OutNode: GraphNode,
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<OutNode, InNode, InEvent> DeltaStore<OutNode, InNode, InEvent>
+where
+    OutNode: GraphNode,
+    InNode: GraphNode,
+    InEvent: GraphEvent,
+{
+    pub fn new() -> Self {
+        DeltaStore {
+            deltas: Vec::new(),
+            dirty: Vec::new(),
+            listed: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, out_node: &OutNode, in_node: &InNode, in_event: &InEvent) {
+        let idx: usize = (*out_node).into();
+        if idx >= self.deltas.len() {
+            self.deltas.resize_with(idx + 1, Vec::new);
+            self.listed.resize(idx + 1, false);
+        }
+        if !self.listed[idx] {
+            self.listed[idx] = true;
+            self.dirty.push(*out_node);
+        }
+        let deltas = &mut self.deltas[idx];
+        match deltas.last_mut() {
+            Some(&mut (l_node, ref mut l_evt)) if l_node == *in_node => {
+                *l_evt = l_evt.merge(*in_event);
+            }
+            _ => deltas.push((*in_node, *in_event)),
+        }
+    }
+
+    /// Removes and returns the deltas of `out_node`.
+    pub fn take(&mut self, out_node: &OutNode) -> Vec<(InNode, InEvent)> {
+        match self.deltas.get_mut((*out_node).into()) {
+            Some(deltas) => std::mem::take(deltas),
+            None => Vec::new(),
+        }
+    }
+
+    /// Visits then removes the deltas of `out_node`.
+    pub fn visit_and_clear<Visitor>(&mut self, out_node: &OutNode, visitor: &mut Visitor)
+    where
+        Visitor: VisitMut<(InNode, InEvent)>,
+    {
+        if let Some(deltas) = self.deltas.get_mut((*out_node).into()) {
+            deltas.drain(..).for_each(|delta| visitor.visit_mut(&delta));
+        }
+    }
+
+    pub fn clear(&mut self, out_node: &OutNode) {
+        if let Some(deltas) = self.deltas.get_mut((*out_node).into()) {
+            deltas.clear();
+        }
+    }
+
+    pub fn clear_all(&mut self) {
+        for out_node in self.dirty.drain(..) {
+            let idx: usize = out_node.into();
+            self.deltas[idx].clear();
+            self.listed[idx] = false;
+        }
+    }
+}
+
+pub struct HandlerOutputBuilder<OutNode, OutEvent, Sched = PriorityScheduler<OutNode>>
 where
     OutNode: GraphNode,
     OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
 {
     last_out: OutNode,
+    idempotent: Vec<bool>,
     _event: PhantomData<OutEvent>,
     _out_node: PhantomData<OutNode>,
+    _sched: PhantomData<Sched>,
 }
 
-impl<OutNode, OutEvent> Default for HandlerOutputBuilder<OutNode, OutEvent>
+impl<OutNode, OutEvent, Sched> Default for HandlerOutputBuilder<OutNode, OutEvent, Sched>
 where
     OutNode: GraphNode,
     OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<OutNode, OutEvent> HandlerOutputBuilder<OutNode, OutEvent>
+impl<OutNode, OutEvent, Sched> HandlerOutputBuilder<OutNode, OutEvent, Sched>
 where
     OutNode: GraphNode,
     OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
 {
     pub fn new() -> Self {
         HandlerOutputBuilder {
             last_out: 0usize.into(),
+            idempotent: Vec::new(),
             _event: PhantomData,
             _out_node: PhantomData,
+            _sched: PhantomData,
+        }
+    }
+
+    /// Changes the order in which the output nodes are popped.
+    pub fn with_scheduler<NewSched>(self) -> HandlerOutputBuilder<OutNode, OutEvent, NewSched>
+    where
+        NewSched: Scheduler<OutNode>,
+    {
+        HandlerOutputBuilder {
+            last_out: self.last_out,
+            idempotent: self.idempotent,
+            _event: PhantomData,
+            _out_node: PhantomData,
+            _sched: PhantomData,
         }
     }
 
@@ -504,54 +1238,280 @@ where
         self.last_out = self.last_out.max(node);
     }
 
-    pub fn finalize(self) -> HandlerOutput<OutNode, OutEvent> {
+    pub fn finalize(mut self) -> HandlerOutput<OutNode, OutEvent, Sched> {
         let len = self.last_out.into() + 1;
+        self.idempotent.resize(len, false);
         HandlerOutput {
             mode: vec![OutEvent::null(); len],
-            queue: PriorityQueue::new(),
+            disabled: vec![false; len],
+            idempotent: self.idempotent,
+            ignored: vec![false; len],
+            running: None,
+            weights: vec![0; len],
+            queue: Sched::default(),
+            trail: Vec::new(),
+            levels: Vec::new(),
         }
     }
 }
 
-pub struct HandlerOutput<OutNode, OutEvent>
+impl<OutNode, OutEvent, Sched> IdempotentOutputNode<OutNode> for HandlerOutputBuilder<OutNode, OutEvent, Sched>
 where
     OutNode: GraphNode,
     OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
+{
+    fn set_idempotent(&mut self, out_node: &OutNode, idempotent: bool) {
+        let idx: usize = (*out_node).into();
+        if idx >= self.idempotent.len() {
+            self.idempotent.resize(idx + 1, false);
+        }
+        self.idempotent[idx] = idempotent;
+        self.add_node(*out_node);
+    }
+
+    fn is_idempotent(&self, out_node: &OutNode) -> bool {
+        self.idempotent.get((*out_node).into()).copied().unwrap_or(false)
+    }
+}
+
+enum OutputTrailEntry<OutNode, OutEvent> {
+    Mode(OutNode, OutEvent),
+    Disabled(OutNode, bool),
+    Pushed(OutNode, Option<i64>),
+    Popped(OutNode, i64),
+}
+
+pub struct HandlerOutput<OutNode, OutEvent, Sched = PriorityScheduler<OutNode>>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
 {
     mode: Vec<OutEvent>,
-    queue: PriorityQueue<OutNode, i64>,
+    disabled: Vec<bool>,
+    idempotent: Vec<bool>,
+    // output nodes ignored by the current group collect
+    ignored: Vec<bool>,
+    // last popped output node
+    running: Option<OutNode>,
+    weights: Vec<u64>,
+    queue: Sched,
+    trail: Vec<OutputTrailEntry<OutNode, OutEvent>>,
+    levels: Vec<usize>,
 }
 
-impl<OutNode, OutEvent> HandlerOutput<OutNode, OutEvent>
+impl<OutNode, OutEvent, Sched> HandlerOutput<OutNode, OutEvent, Sched>
 where
     OutNode: GraphNode,
     OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
 {
-    pub fn builder() -> HandlerOutputBuilder<OutNode, OutEvent> {
+    pub fn builder() -> HandlerOutputBuilder<OutNode, OutEvent, Sched> {
         HandlerOutputBuilder::new()
     }
 
+    pub fn add_node(&mut self, node: OutNode) {
+        let idx: usize = node.into();
+        if idx >= self.mode.len() {
+            self.mode.resize(idx + 1, OutEvent::null());
+            self.disabled.resize(idx + 1, false);
+            self.idempotent.resize(idx + 1, false);
+            self.ignored.resize(idx + 1, false);
+            self.weights.resize(idx + 1, 0);
+        }
+    }
+
     pub fn collect_out_event(&mut self, out: &OutCostEventLink<OutNode, OutEvent>, ignored_out: Option<OutNode>) {
-        unsafe {
-            let out_node = out.idx;
-            let ignored = match ignored_out {
-                Some(ignored_out) if ignored_out == out_node => {
-                    true
-                },
-                _ => false,
-            };
-            if !ignored {
-                self.queue.push(out_node, out.cost);
-                let curr_state = self.mode.get_unchecked_mut(out_node.into());
-                *curr_state = curr_state.merge(out.event);
+        self.try_collect_out_event(out, ignored_out);
+    }
+
+    /// The output node popped last, `None` if the queue was empty or after a `pop_level`.
+    pub fn running(&self) -> Option<OutNode> {
+        self.running
+    }
+
+    /// Forgets the running node, e.g. when a node of another output runs.
+    pub fn clear_running(&mut self) {
+        self.running = None;
+    }
+
+    /// Marks the given output nodes as ignored by the following collects, until unmarked.
+    /// The output nodes without links yet are never collected and are skipped.
+    pub fn set_ignored(&mut self, out_nodes: &[OutNode], ignored: bool) {
+        for out_node in out_nodes {
+            if let Some(flag) = self.ignored.get_mut((*out_node).into()) {
+                *flag = ignored;
             }
         }
     }
 
+    /// Same as `collect_out_event`, returns false if the output node is ignored or disabled.
+    pub fn try_collect_out_event(
+        &mut self,
+        out: &OutCostEventLink<OutNode, OutEvent>,
+        ignored_out: Option<OutNode>,
+    ) -> bool {
+        let out_node = out.idx;
+        let out_idx: usize = out_node.into();
+        let ignored = match ignored_out {
+            Some(ignored_out) if ignored_out == out_node => {
+                true
+            },
+            _ => {
+                self.disabled[out_idx]
+                    || self.ignored[out_idx]
+                    || (self.running == Some(out_node) && self.idempotent[out_idx])
+            },
+        };
+        if !ignored {
+            let prev_cost = self.queue.schedule(out_node, out.cost);
+            let curr_state = &mut self.mode[out_idx];
+            let prev_state = *curr_state;
+            *curr_state = curr_state.merge(out.event);
+            self.record(OutputTrailEntry::Pushed(out_node, prev_cost));
+            self.record(OutputTrailEntry::Mode(out_node, prev_state));
+        }
+        !ignored
+    }
+
+    #[inline]
+    fn record(&mut self, entry: OutputTrailEntry<OutNode, OutEvent>) {
+        if !self.levels.is_empty() {
+            self.trail.push(entry);
+        }
+    }
+
     #[inline]
     pub fn pop(&mut self) -> Option<(OutNode, OutEvent)> {
-        let (out_idx, _cost) = self.queue.pop()?;
-        let event = self.mode[out_idx.into()].nullify();
+        self.running = None;
+        self.discard_disabled();
+        let (out_idx, cost) = self.queue.pop()?;
+        let event = self.nullify_popped(out_idx, cost);
+        self.running = Some(out_idx);
         Some((out_idx, event))
     }
+
+    /// The output node returned by the next `pop`, with its cost.
+    pub fn peek(&mut self) -> Option<(OutNode, i64)> {
+        self.discard_disabled();
+        self.queue.peek()
+    }
+
+    // Disabled nodes are discarded lazily when they reach the front of the queue.
+    fn discard_disabled(&mut self) {
+        loop {
+            match self.queue.peek() {
+                Some((out_idx, _)) if self.disabled[out_idx.into()] => {}
+                _ => return,
+            }
+            let (out_idx, cost) = self.queue.pop().expect("Peeked node");
+            self.nullify_popped(out_idx, cost);
+        }
+    }
+
+    fn nullify_popped(&mut self, out_idx: OutNode, cost: i64) -> OutEvent {
+        let event = self.mode[out_idx.into()].nullify();
+        self.record(OutputTrailEntry::Popped(out_idx, cost));
+        self.record(OutputTrailEntry::Mode(out_idx, event));
+        event
+    }
+}
+
+impl<OutNode, OutEvent, Sched> DisableOutputNode<OutNode> for HandlerOutput<OutNode, OutEvent, Sched>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
+{
+    // Disabled nodes already in the queue are discarded lazily by `pop` and `peek`.
+    fn disable(&mut self, out_node: &OutNode) {
+        self.add_node(*out_node);
+        let prev = std::mem::replace(&mut self.disabled[(*out_node).into()], true);
+        self.record(OutputTrailEntry::Disabled(*out_node, prev));
+    }
+
+    fn enable(&mut self, out_node: &OutNode) {
+        self.add_node(*out_node);
+        let prev = std::mem::replace(&mut self.disabled[(*out_node).into()], false);
+        self.record(OutputTrailEntry::Disabled(*out_node, prev));
+    }
+
+    fn is_disabled(&self, out_node: &OutNode) -> bool {
+        self.disabled.get((*out_node).into()).copied().unwrap_or(false)
+    }
+}
+
+impl<OutNode, OutEvent, Sched> IdempotentOutputNode<OutNode> for HandlerOutput<OutNode, OutEvent, Sched>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
+{
+    fn set_idempotent(&mut self, out_node: &OutNode, idempotent: bool) {
+        self.add_node(*out_node);
+        self.idempotent[(*out_node).into()] = idempotent;
+    }
+
+    fn is_idempotent(&self, out_node: &OutNode) -> bool {
+        self.idempotent.get((*out_node).into()).copied().unwrap_or(false)
+    }
+}
+
+impl<OutNode, OutEvent, Sched> FailureHandler<OutNode> for HandlerOutput<OutNode, OutEvent, Sched>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
+{
+    fn notify_failure(&mut self, out_node: &OutNode) {
+        self.add_node(*out_node);
+        self.weights[(*out_node).into()] += 1;
+    }
+}
+
+impl<OutNode, OutEvent, Sched> OutputWeight<OutNode> for HandlerOutput<OutNode, OutEvent, Sched>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
+{
+    fn weight(&self, out_node: &OutNode) -> u64 {
+        self.weights.get((*out_node).into()).copied().unwrap_or(0)
+    }
+}
+
+impl<OutNode, OutEvent, Sched> Backtrackable for HandlerOutput<OutNode, OutEvent, Sched>
+where
+    OutNode: GraphNode,
+    OutEvent: GraphEvent,
+    Sched: Scheduler<OutNode>,
+{
+    fn push_level(&mut self) {
+        self.levels.push(self.trail.len());
+    }
+
+    fn pop_level(&mut self) {
+        let mark = match self.levels.pop() {
+            Some(mark) => mark,
+            None => return,
+        };
+        self.running = None;
+        for entry in self.trail.drain(mark..).rev() {
+            match entry {
+                OutputTrailEntry::Mode(node, event) => {
+                    self.mode[node.into()] = event;
+                }
+                OutputTrailEntry::Disabled(node, disabled) => {
+                    self.disabled[node.into()] = disabled;
+                }
+                OutputTrailEntry::Pushed(node, prev_cost) => {
+                    self.queue.undo_schedule(node, prev_cost);
+                }
+                OutputTrailEntry::Popped(node, cost) => {
+                    self.queue.undo_pop(node, cost);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,353 @@
+use crate::GraphNode;
+use priority_queue::PriorityQueue;
+
+use std::collections::VecDeque;
+
+/// Order in which the collected output nodes are popped.
+///
+/// The `undo_*` functions are called in the reverse order of the operations they undo.
+pub trait Scheduler<Node>: Default
+where
+    Node: GraphNode,
+{
+    /// Schedules `node`, returns its previous cost if it was already scheduled.
+    fn schedule(&mut self, node: Node, cost: i64) -> Option<i64>;
+    fn pop(&mut self) -> Option<(Node, i64)>;
+    /// The node returned by the next `pop`, with its cost.
+    fn peek(&self) -> Option<(Node, i64)>;
+    /// Undoes the last `schedule` of `node` that returned `prev`.
+    fn undo_schedule(&mut self, node: Node, prev: Option<i64>);
+    /// Undoes the last `pop` that returned `(node, cost)`.
+    fn undo_pop(&mut self, node: Node, cost: i64);
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Cost of the scheduled nodes, `None` if not scheduled.
+#[derive(Default)]
+struct Scheduled {
+    costs: Vec<Option<i64>>,
+}
+
+impl Scheduled {
+    fn get(&self, idx: usize) -> Option<i64> {
+        self.costs.get(idx).copied().flatten()
+    }
+
+    fn set(&mut self, idx: usize, cost: Option<i64>) {
+        if idx >= self.costs.len() {
+            self.costs.resize(idx + 1, None);
+        }
+        self.costs[idx] = cost;
+    }
+}
+
+/// Pops the node with the highest cost first. Scheduling a node again overrides its cost.
+pub struct PriorityScheduler<Node>
+where
+    Node: GraphNode,
+{
+    queue: PriorityQueue<Node, i64>,
+}
+
+impl<Node> Default for PriorityScheduler<Node>
+where
+    Node: GraphNode,
+{
+    fn default() -> Self {
+        PriorityScheduler {
+            queue: PriorityQueue::new(),
+        }
+    }
+}
+
+impl<Node> Scheduler<Node> for PriorityScheduler<Node>
+where
+    Node: GraphNode,
+{
+    fn schedule(&mut self, node: Node, cost: i64) -> Option<i64> {
+        self.queue.push(node, cost)
+    }
+
+    fn pop(&mut self) -> Option<(Node, i64)> {
+        self.queue.pop()
+    }
+
+    fn peek(&self) -> Option<(Node, i64)> {
+        self.queue.peek().map(|(node, cost)| (*node, *cost))
+    }
+
+    fn undo_schedule(&mut self, node: Node, prev: Option<i64>) {
+        match prev {
+            Some(cost) => {
+                self.queue.push(node, cost);
+            }
+            None => {
+                self.queue.remove(&node);
+            }
+        }
+    }
+
+    fn undo_pop(&mut self, node: Node, cost: i64) {
+        self.queue.push(node, cost);
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+/// Pops the nodes in their scheduling order. A node scheduled again keeps its position and cost.
+pub struct FifoScheduler<Node> {
+    queue: VecDeque<Node>,
+    scheduled: Scheduled,
+}
+
+impl<Node> Default for FifoScheduler<Node> {
+    fn default() -> Self {
+        FifoScheduler {
+            queue: VecDeque::new(),
+            scheduled: Scheduled::default(),
+        }
+    }
+}
+
+impl<Node> Scheduler<Node> for FifoScheduler<Node>
+where
+    Node: GraphNode,
+{
+    fn schedule(&mut self, node: Node, cost: i64) -> Option<i64> {
+        let prev = self.scheduled.get(node.into());
+        if prev.is_none() {
+            self.scheduled.set(node.into(), Some(cost));
+            self.queue.push_back(node);
+        }
+        prev
+    }
+
+    fn pop(&mut self) -> Option<(Node, i64)> {
+        let node = self.queue.pop_front()?;
+        let cost = self.scheduled.get(node.into()).expect("Scheduled node");
+        self.scheduled.set(node.into(), None);
+        Some((node, cost))
+    }
+
+    fn peek(&self) -> Option<(Node, i64)> {
+        let node = *self.queue.front()?;
+        Some((node, self.scheduled.get(node.into()).expect("Scheduled node")))
+    }
+
+    fn undo_schedule(&mut self, node: Node, prev: Option<i64>) {
+        if prev.is_none() {
+            self.queue.pop_back();
+            self.scheduled.set(node.into(), None);
+        }
+    }
+
+    fn undo_pop(&mut self, node: Node, cost: i64) {
+        self.scheduled.set(node.into(), Some(cost));
+        self.queue.push_front(node);
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+/// Pops the most recently scheduled node first. A node scheduled again keeps its position and cost.
+pub struct LifoScheduler<Node> {
+    stack: Vec<Node>,
+    scheduled: Scheduled,
+}
+
+impl<Node> Default for LifoScheduler<Node> {
+    fn default() -> Self {
+        LifoScheduler {
+            stack: Vec::new(),
+            scheduled: Scheduled::default(),
+        }
+    }
+}
+
+impl<Node> Scheduler<Node> for LifoScheduler<Node>
+where
+    Node: GraphNode,
+{
+    fn schedule(&mut self, node: Node, cost: i64) -> Option<i64> {
+        let prev = self.scheduled.get(node.into());
+        if prev.is_none() {
+            self.scheduled.set(node.into(), Some(cost));
+            self.stack.push(node);
+        }
+        prev
+    }
+
+    fn pop(&mut self) -> Option<(Node, i64)> {
+        let node = self.stack.pop()?;
+        let cost = self.scheduled.get(node.into()).expect("Scheduled node");
+        self.scheduled.set(node.into(), None);
+        Some((node, cost))
+    }
+
+    fn peek(&self) -> Option<(Node, i64)> {
+        let node = *self.stack.last()?;
+        Some((node, self.scheduled.get(node.into()).expect("Scheduled node")))
+    }
+
+    fn undo_schedule(&mut self, node: Node, prev: Option<i64>) {
+        if prev.is_none() {
+            self.stack.pop();
+            self.scheduled.set(node.into(), None);
+        }
+    }
+
+    fn undo_pop(&mut self, node: Node, cost: i64) {
+        self.scheduled.set(node.into(), Some(cost));
+        self.stack.push(node);
+    }
+
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+/// Usual cost classes of the propagators, from the cheapest to the most expensive.
+///
+/// Converted to a cost such that cheaper classes are popped first by the `PriorityScheduler` and by
+/// the `MultiLevelScheduler`s with at least `COUNT` levels, like the `CostClassScheduler`.
+/// The `FifoScheduler` and `LifoScheduler` ignore the costs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CostClass {
+    Unary,
+    Binary,
+    Linear,
+    Quadratic,
+    Global,
+}
+
+impl CostClass {
+    pub const COUNT: usize = 5;
+}
+
+impl From<CostClass> for i64 {
+    fn from(class: CostClass) -> Self {
+        match class {
+            CostClass::Unary => 4,
+            CostClass::Binary => 3,
+            CostClass::Linear => 2,
+            CostClass::Quadratic => 1,
+            CostClass::Global => 0,
+        }
+    }
+}
+
+/// One FIFO queue per level as in Gecode and Choco propagation engines, with O(1) schedule and pop.
+///
+/// The level of a node is its cost clamped to `[0, LEVELS - 1]`, the highest level is popped first.
+/// A node scheduled again keeps its level and position. At most 64 levels are supported.
+pub struct MultiLevelScheduler<Node, const LEVELS: usize = 8> {
+    levels: Vec<VecDeque<Node>>,
+    // bit `i` is set iff the level `i` is not empty
+    non_empty: u64,
+    scheduled: Scheduled,
+    len: usize,
+}
+
+/// Bucket queue with one level per `CostClass`.
+pub type CostClassScheduler<Node> = MultiLevelScheduler<Node, { CostClass::COUNT }>;
+
+impl<Node, const LEVELS: usize> MultiLevelScheduler<Node, LEVELS> {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn level(cost: i64) -> usize {
+        cost.max(0).min(LEVELS as i64 - 1) as usize
+    }
+
+    fn push_back(&mut self, level: usize, node: Node) {
+        self.levels[level].push_back(node);
+        self.non_empty |= 1 << level;
+        self.len += 1;
+    }
+
+    fn push_front(&mut self, level: usize, node: Node) {
+        self.levels[level].push_front(node);
+        self.non_empty |= 1 << level;
+        self.len += 1;
+    }
+
+    // highest non empty level
+    fn top_level(&self) -> Option<usize> {
+        if self.non_empty == 0 {
+            None
+        } else {
+            Some(63 - self.non_empty.leading_zeros() as usize)
+        }
+    }
+
+    fn removed_from(&mut self, level: usize) {
+        if self.levels[level].is_empty() {
+            self.non_empty &= !(1 << level);
+        }
+        self.len -= 1;
+    }
+}
+
+impl<Node, const LEVELS: usize> Default for MultiLevelScheduler<Node, LEVELS> {
+    fn default() -> Self {
+        assert!(LEVELS > 0 && LEVELS <= 64, "Between 1 and 64 levels");
+        MultiLevelScheduler {
+            levels: (0..LEVELS).map(|_| VecDeque::new()).collect(),
+            non_empty: 0,
+            scheduled: Scheduled::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<Node, const LEVELS: usize> Scheduler<Node> for MultiLevelScheduler<Node, LEVELS>
+where
+    Node: GraphNode,
+{
+    fn schedule(&mut self, node: Node, cost: i64) -> Option<i64> {
+        let prev = self.scheduled.get(node.into());
+        if prev.is_none() {
+            self.scheduled.set(node.into(), Some(cost));
+            self.push_back(Self::level(cost), node);
+        }
+        prev
+    }
+
+    fn pop(&mut self) -> Option<(Node, i64)> {
+        let level = self.top_level()?;
+        let node = self.levels[level].pop_front().expect("Non empty level");
+        self.removed_from(level);
+        let cost = self.scheduled.get(node.into()).expect("Scheduled node");
+        self.scheduled.set(node.into(), None);
+        Some((node, cost))
+    }
+
+    fn peek(&self) -> Option<(Node, i64)> {
+        let node = *self.levels[self.top_level()?].front().expect("Non empty level");
+        Some((node, self.scheduled.get(node.into()).expect("Scheduled node")))
+    }
+
+    fn undo_schedule(&mut self, node: Node, prev: Option<i64>) {
+        if prev.is_none() {
+            let cost = self.scheduled.get(node.into()).expect("Scheduled node");
+            let level = Self::level(cost);
+            self.levels[level].pop_back();
+            self.removed_from(level);
+            self.scheduled.set(node.into(), None);
+        }
+    }
+
+    fn undo_pop(&mut self, node: Node, cost: i64) {
+        self.scheduled.set(node.into(), Some(cost));
+        self.push_front(Self::level(cost), node);
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
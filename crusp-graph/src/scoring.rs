@@ -0,0 +1,219 @@
+use crate::{GraphEvent, GraphNode, LookEvent};
+
+// Nodes looked at since the last conflict or fixpoint.
+struct Touched<Node> {
+    nodes: Vec<Node>,
+    seen: Vec<bool>,
+}
+
+impl<Node: GraphNode> Touched<Node> {
+    fn new(len: usize) -> Self {
+        Touched {
+            nodes: Vec::new(),
+            seen: vec![false; len],
+        }
+    }
+
+    fn touch(&mut self, node: &Node) {
+        let idx: usize = (*node).into();
+        if idx >= self.seen.len() {
+            self.seen.resize(idx + 1, false);
+        }
+        if !self.seen[idx] {
+            self.seen[idx] = true;
+            self.nodes.push(*node);
+        }
+    }
+
+    fn drain(&mut self) -> Vec<Node> {
+        for node in self.nodes.iter() {
+            self.seen[(*node).into()] = false;
+        }
+        std::mem::take(&mut self.nodes)
+    }
+}
+
+fn best_score<Node, F>(scores: &[f64], mut is_fixed: F) -> Option<Node>
+where
+    Node: GraphNode,
+    F: FnMut(&Node) -> bool,
+{
+    let mut best: Option<(Node, f64)> = None;
+    for (idx, &score) in scores.iter().enumerate() {
+        let node: Node = idx.into();
+        if is_fixed(&node) {
+            continue;
+        }
+        match best {
+            Some((_, best_score)) if best_score >= score => {}
+            _ => best = Some((node, score)),
+        }
+    }
+    best.map(|(node, _)| node)
+}
+
+/// Conflict-History-Based scores of the input nodes (Liang et al., 2016).
+///
+/// Every node looked at is rewarded when `conflict` or `fixpoint` is called,
+/// with a larger reward for nodes involved in recent conflicts.
+pub struct ChbScores<Node> {
+    scores: Vec<f64>,
+    last_conflict: Vec<u64>,
+    conflicts: u64,
+    step_size: f64,
+    step_decay: f64,
+    min_step_size: f64,
+    touched: Touched<Node>,
+}
+
+impl<Node> ChbScores<Node>
+where
+    Node: GraphNode,
+{
+    /// Scores for `len` input nodes, using a step size starting at 0.4 and decreasing by
+    /// 1e-6 at each conflict until 0.06.
+    pub fn new(len: usize) -> Self {
+        ChbScores {
+            scores: vec![0.0; len],
+            last_conflict: vec![0; len],
+            conflicts: 0,
+            step_size: 0.4,
+            step_decay: 1e-6,
+            min_step_size: 0.06,
+            touched: Touched::new(len),
+        }
+    }
+
+    pub fn with_step_size(mut self, step_size: f64, step_decay: f64, min_step_size: f64) -> Self {
+        self.step_size = step_size;
+        self.step_decay = step_decay;
+        self.min_step_size = min_step_size;
+        self
+    }
+
+    pub fn score(&self, node: &Node) -> f64 {
+        self.scores.get((*node).into()).copied().unwrap_or(0.0)
+    }
+
+    /// The node with the highest score among those that are not fixed.
+    pub fn best<F>(&self, is_fixed: F) -> Option<Node>
+    where
+        F: FnMut(&Node) -> bool,
+    {
+        best_score(&self.scores, is_fixed)
+    }
+
+    /// Rewards the nodes looked at since the last call after a failed propagation.
+    pub fn conflict(&mut self) {
+        self.conflicts += 1;
+        self.reward(1.0, true);
+        self.step_size = (self.step_size - self.step_decay).max(self.min_step_size);
+    }
+
+    /// Rewards the nodes looked at since the last call after a successful propagation.
+    pub fn fixpoint(&mut self) {
+        self.reward(0.9, false);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn reward(&mut self, multiplier: f64, in_conflict: bool) {
+        let conflicts = self.conflicts;
+        for node in self.touched.drain() {
+            let idx: usize = node.into();
+            if idx >= self.scores.len() {
+                self.scores.resize(idx + 1, 0.0);
+                self.last_conflict.resize(idx + 1, 0);
+            }
+            if in_conflict {
+                self.last_conflict[idx] = conflicts;
+            }
+            let age = (conflicts - self.last_conflict[idx] + 1) as f64;
+            let reward = multiplier / age;
+            self.scores[idx] = (1.0 - self.step_size) * self.scores[idx] + self.step_size * reward;
+        }
+    }
+}
+
+impl<Node, Event> LookEvent<Node, Event> for ChbScores<Node>
+where
+    Node: GraphNode,
+    Event: GraphEvent,
+{
+    fn look_event(&mut self, node: &Node, _event: &Event) {
+        self.touched.touch(node);
+    }
+}
+
+/// VSIDS-like activities of the input nodes.
+///
+/// The nodes looked at since the last call are bumped by `conflict`, and forgotten by `fixpoint`.
+/// The bump increases after each conflict so that recent conflicts weight more.
+pub struct ActivityScores<Node> {
+    activities: Vec<f64>,
+    bump: f64,
+    decay: f64,
+    touched: Touched<Node>,
+}
+
+impl<Node> ActivityScores<Node>
+where
+    Node: GraphNode,
+{
+    const RESCALE: f64 = 1e100;
+
+    /// Activities for `len` input nodes, decaying by a factor 0.95 at each conflict.
+    pub fn new(len: usize) -> Self {
+        ActivityScores {
+            activities: vec![0.0; len],
+            bump: 1.0,
+            decay: 0.95,
+            touched: Touched::new(len),
+        }
+    }
+
+    pub fn with_decay(mut self, decay: f64) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    pub fn activity(&self, node: &Node) -> f64 {
+        self.activities.get((*node).into()).copied().unwrap_or(0.0)
+    }
+
+    /// The node with the highest activity among those that are not fixed.
+    pub fn best<F>(&self, is_fixed: F) -> Option<Node>
+    where
+        F: FnMut(&Node) -> bool,
+    {
+        best_score(&self.activities, is_fixed)
+    }
+
+    pub fn conflict(&mut self) {
+        for node in self.touched.drain() {
+            let idx: usize = node.into();
+            if idx >= self.activities.len() {
+                self.activities.resize(idx + 1, 0.0);
+            }
+            self.activities[idx] += self.bump;
+            if self.activities[idx] > Self::RESCALE {
+                self.activities.iter_mut().for_each(|activity| *activity /= Self::RESCALE);
+                self.bump /= Self::RESCALE;
+            }
+        }
+        self.bump /= self.decay;
+    }
+
+    pub fn fixpoint(&mut self) {
+        self.touched.drain();
+    }
+}
+
+impl<Node, Event> LookEvent<Node, Event> for ActivityScores<Node>
+where
+    Node: GraphNode,
+    Event: GraphEvent,
+{
+    fn look_event(&mut self, node: &Node, _event: &Event) {
+        self.touched.touch(node);
+    }
+}
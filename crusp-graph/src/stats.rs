@@ -0,0 +1,117 @@
+use std::fmt;
+
+/// Distribution of a count over the nodes of a graph, e.g. their degrees.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    pub nodes: usize,
+    /// Sum of the counts, i.e. the number of edges for degrees.
+    pub total: usize,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    /// `histogram[0]` is the number of nodes with a count of 0
+    /// and `histogram[i]` the number of nodes with a count in `[2^(i-1), 2^i)`.
+    pub histogram: Vec<usize>,
+}
+
+impl Distribution {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_counts<I>(counts: I) -> Self
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut distribution = Distribution {
+            nodes: 0,
+            total: 0,
+            min: usize::MAX,
+            max: 0,
+            mean: 0.0,
+            histogram: Vec::new(),
+        };
+        for count in counts {
+            distribution.nodes += 1;
+            distribution.total += count;
+            distribution.min = distribution.min.min(count);
+            distribution.max = distribution.max.max(count);
+            let bucket = Self::bucket(count);
+            if bucket >= distribution.histogram.len() {
+                distribution.histogram.resize(bucket + 1, 0);
+            }
+            distribution.histogram[bucket] += 1;
+        }
+        if distribution.nodes == 0 {
+            distribution.min = 0;
+        } else {
+            distribution.mean = distribution.total as f64 / distribution.nodes as f64;
+        }
+        distribution
+    }
+
+    // number of significant bits
+    fn bucket(count: usize) -> usize {
+        let mut bucket = 0;
+        let mut rest = count;
+        while rest > 0 {
+            bucket += 1;
+            rest >>= 1;
+        }
+        bucket
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} in total, min {}, mean {:.2}, max {}",
+            self.nodes, self.total, self.min, self.mean, self.max
+        )?;
+        for (bucket, &nodes) in self.histogram.iter().enumerate() {
+            if nodes == 0 {
+                continue;
+            }
+            match bucket {
+                0 => write!(f, "\n    0: {}", nodes)?,
+                1 => write!(f, "\n    1: {}", nodes)?,
+                _ => write!(f, "\n    {}..{}: {}", 1usize << (bucket - 1), (1usize << bucket) - 1, nodes)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Links of the input nodes of a `LazyInputEventGraph`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputStats {
+    /// Number of links of each input node.
+    pub degrees: Distribution,
+    /// Number of distinct link events of each input node,
+    /// the events subsumed under each other being counted once.
+    pub event_masks: Distribution,
+}
+
+impl fmt::Display for InputStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "links: {}\n  event masks: {}", self.degrees, self.event_masks)
+    }
+}
+
+/// Statistics of a derived graph, by field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphStats {
+    /// Number of input nodes of each output node, all the inputs included.
+    pub outputs: Vec<(&'static str, Distribution)>,
+    pub inputs: Vec<(&'static str, InputStats)>,
+}
+
+impl fmt::Display for GraphStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, degrees) in self.outputs.iter() {
+            writeln!(f, "output {}: {}", name, degrees)?;
+        }
+        for (name, stats) in self.inputs.iter() {
+            writeln!(f, "input {}: {}", name, stats)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use crusp_core::{ConstraintId, VariableId};
+use crusp_graph::*;
+
+struct Collect(Vec<usize>);
+
+impl VisitMut<VariableId> for Collect {
+    fn visit_mut(&mut self, var: &VariableId) {
+        self.0.push((*var).into());
+    }
+}
+
+fn ins(graph: &AdjacentListGraph<ConstraintId, VariableId>, constraint: usize) -> Vec<usize> {
+    let mut collect = Collect(Vec::new());
+    graph.visit_in_nodes(&ConstraintId::from(constraint), &mut collect);
+    collect.0
+}
+
+pub fn main() {
+    let mut builder = AdjacentListGraph::<ConstraintId, VariableId>::builder();
+    for &(constraint, var) in [(2, 5), (0, 3), (2, 1), (0, 3), (2, 4), (0, 0)].iter() {
+        builder.add_node(&ConstraintId::from(constraint), &VariableId::from(var));
+    }
+    let mut graph = builder.finalize();
+    // destinations are sorted and deduplicated
+    assert_eq!(ins(&graph, 0), vec![0, 3]);
+    assert_eq!(ins(&graph, 1), Vec::<usize>::new());
+    assert_eq!(ins(&graph, 2), vec![1, 4, 5]);
+    assert_eq!(ins(&graph, 7), Vec::<usize>::new());
+    assert_eq!(graph.degree(&ConstraintId::from(0)), 2);
+    assert_eq!(graph.degree(&ConstraintId::from(1)), 0);
+    assert_eq!(graph.degree(&ConstraintId::from(2)), 3);
+    assert_eq!(graph.degree(&ConstraintId::from(7)), 0);
+
+    // insertions once finalized keep the destinations sorted
+    graph.add_node(&ConstraintId::from(0), &VariableId::from(2));
+    graph.add_node(&ConstraintId::from(0), &VariableId::from(3));
+    graph.add_node(&ConstraintId::from(1), &VariableId::from(6));
+    graph.add_node(&ConstraintId::from(4), &VariableId::from(1));
+    assert_eq!(ins(&graph, 0), vec![0, 2, 3]);
+    assert_eq!(ins(&graph, 1), vec![6]);
+    assert_eq!(ins(&graph, 2), vec![1, 4, 5]);
+    assert_eq!(ins(&graph, 3), Vec::<usize>::new());
+    assert_eq!(ins(&graph, 4), vec![1]);
+    assert_eq!(graph.degree(&ConstraintId::from(0)), 3);
+    assert_eq!(graph.degree(&ConstraintId::from(4)), 1);
+    let constraint = ConstraintId::from(0);
+    let vars = |vars: &[usize]| vars.iter().map(|&var| VariableId::from(var)).collect::<Vec<_>>();
+    assert_eq!(graph.in_nodes(&constraint).len(), 3);
+    assert_eq!(graph.in_nodes(&constraint).copied().collect::<Vec<_>>(), vars(&[0, 2, 3]));
+    assert_eq!(graph.in_nodes(&constraint).rev().copied().collect::<Vec<_>>(), vars(&[3, 2, 0]));
+    assert_eq!(graph.sources(), 5);
+    assert_eq!(graph.stats().total, 8);
+
+    let empty = AdjacentListGraph::<ConstraintId, VariableId>::builder().finalize();
+    assert_eq!(empty.degree(&ConstraintId::from(0)), 0);
+}
@@ -0,0 +1,78 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let oe2 = FdEvent::FIX;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let on2 = Prop(2);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe2, &in1, &ie1, 1i64);
+    graph.add_event(&on2, &oe1, &in2, &ie2, 2i64);
+    let mut graph = graph.finalize();
+
+    // queue and disabled flags are restored
+    graph.notify(&in1, &ie1);
+    graph.collect(None);
+    graph.push_level();
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on1, oe2)));
+    graph.disable(&on0);
+    graph.notify(&in2, &ie2);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on2, oe1)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+    graph.pop_level();
+    assert!(!graph.is_disabled(&on0));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on1, oe2)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on0, oe1)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    // pending events are restored
+    graph.notify(&in2, &ie2);
+    graph.push_level();
+    graph.push_level();
+    graph.notify(&in1, &ie1);
+    graph.pop_level();
+    graph.disable(&on2);
+    graph.pop_level();
+    assert!(!graph.is_disabled(&on2));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on2, oe1)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    // nothing to restore without level
+    graph.pop_level();
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+}
@@ -0,0 +1,91 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+#[derive(Debug)]
+pub struct Changed {
+    pub n1: Vec<Var>,
+    pub n2: Vec<BoolVar>,
+}
+
+impl VisitMut<Var> for Changed {
+    fn visit_mut(&mut self, t: &Var) {
+        self.n1.push(*t);
+    }
+}
+
+impl VisitMut<BoolVar> for Changed {
+    fn visit_mut(&mut self, t: &BoolVar) {
+        self.n2.push(*t);
+    }
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let on0 = Prop(0);
+    let in10 = Var(0);
+    let in11 = Var(1);
+    let ie1 = FdEvent::BOUNDS;
+    let ie0 = FdEvent::null();
+    let in20 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in10, &ie1, 0i64);
+    graph.add_event(&on0, &oe1, &in11, &ie1, 0i64);
+    graph.add_event(&on0, &oe1, &in20, &ie2, 0i64);
+    let mut graph = graph.finalize();
+    assert_eq!(graph.timestamp(), 0);
+    assert_eq!(graph.last_change(&in10), 0);
+    assert!(!graph.peek_change(&in10));
+
+    // the clock is shared between input kinds
+    graph.notify(&in10, &ie1);
+    graph.notify(&in20, &ie2);
+    graph.notify(&in10, &ie0);
+    assert_eq!(graph.timestamp(), 2);
+    assert_eq!(graph.last_change(&in10), 1);
+    assert_eq!(graph.last_change(&in20), 2);
+    assert_eq!(graph.last_change(&in11), 0);
+    assert!(graph.changed_since(&in20, 1));
+    assert!(!graph.changed_since(&in10, 1));
+
+    assert!(graph.peek_change(&in10));
+    assert!(!graph.peek_change(&in10));
+    assert!(!graph.peek_change(&in11));
+
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on0, oe1)));
+    let ran_at = graph.timestamp();
+    graph.notify(&in11, &ie1);
+    let mut changed = Changed { n1: Vec::new(), n2: Vec::new() };
+    graph.visit_all_changed_in_nodes(&on0, ran_at, &mut changed);
+    assert_eq!(changed.n1, vec![in11]);
+    assert!(changed.n2.is_empty());
+    let mut changed = Changed { n1: Vec::new(), n2: Vec::new() };
+    graph.visit_all_changed_in_nodes(&on0, 0, &mut changed);
+    assert_eq!(changed.n1, vec![in10, in11]);
+    assert_eq!(changed.n2, vec![in20]);
+    let mut changed = Changed { n1: Vec::new(), n2: Vec::new() };
+    VisitChangedOutputsNode::<Prop, BoolVar>::visit_changed_in_nodes(&graph, &on0, 0, &mut changed);
+    assert!(changed.n1.is_empty());
+    assert_eq!(changed.n2, vec![in20]);
+    assert!(graph.peek_change(&in11));
+}
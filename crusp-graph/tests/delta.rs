@@ -0,0 +1,109 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+#[derive(Debug, Default)]
+pub struct Deltas {
+    pub d1: Vec<(usize, FdEvent)>,
+    pub d2: Vec<(usize, FlagEvent<u8>)>,
+}
+
+impl VisitMut<(Var, FdEvent)> for Deltas {
+    fn visit_mut(&mut self, t: &(Var, FdEvent)) {
+        self.d1.push((t.0.into(), t.1));
+    }
+}
+
+impl VisitMut<(BoolVar, FlagEvent<u8>)> for Deltas {
+    fn visit_mut(&mut self, t: &(BoolVar, FlagEvent<u8>)) {
+        self.d2.push((t.0.into(), t.1));
+    }
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let in10 = Var(0);
+    let in11 = Var(1);
+    let ie1 = FdEvent::BOUNDS;
+    let lb = FdEvent::LB;
+    let ub = FdEvent::UB;
+    let in20 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in10, &ie1, 1i64);
+    graph.add_event(&on0, &oe1, &in11, &ie1, 1i64);
+    graph.add_event(&on0, &oe1, &in20, &ie2, 1i64);
+    graph.add_event(&on1, &oe1, &in11, &ie1, 0i64);
+    let mut graph = graph.finalize();
+
+    graph.notify(&in10, &lb);
+    graph.notify(&in11, &lb);
+    graph.notify(&in10, &ub);
+    graph.notify(&in20, &ie2);
+    let mut deltas = Deltas::default();
+    let event = graph.collect_delta_and_pop(None, &mut deltas);
+    assert_eq!(event, Some((on0, oe1)));
+    assert_eq!(deltas.d1, vec![(0, lb.merge(ub)), (1, lb)]);
+    assert_eq!(deltas.d2, vec![(0, ie2)]);
+
+    // deltas accumulate and merge until the output node is popped
+    graph.notify(&in11, &ub);
+    let mut deltas = Deltas::default();
+    let event = graph.collect_delta_and_pop(Some(on0), &mut deltas);
+    assert_eq!(event, Some((on1, oe1)));
+    assert_eq!(deltas.d1, vec![(1, lb.merge(ub))]);
+    assert!(deltas.d2.is_empty());
+    let mut deltas = Deltas::default();
+    let event = graph.collect_delta_and_pop_not_ignored(&mut deltas);
+    assert_eq!(event, None);
+    assert!(deltas.d1.is_empty());
+
+    // disabled output nodes drop their deltas, pending events are restored
+    graph.notify(&in20, &ie2);
+    let mut deltas = Deltas::default();
+    graph.push_level();
+    graph.disable(&on0);
+    let event = graph.collect_delta_and_pop(None, &mut deltas);
+    assert_eq!(event, None);
+    graph.pop_level();
+    graph.notify(&in10, &lb);
+    let event = graph.collect_delta_and_pop(None, &mut deltas);
+    assert_eq!(event, Some((on0, oe1)));
+    assert_eq!(deltas.d1, vec![(0, lb)]);
+    assert_eq!(deltas.d2, vec![(0, ie2)]);
+
+    // output nodes popped without their deltas drop them
+    graph.notify(&in11, &lb);
+    let mut deltas = Deltas::default();
+    let event = graph.collect_delta_and_pop(None, &mut deltas);
+    assert_eq!(event, Some((on0, oe1)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on1, oe1)));
+    graph.notify(&in11, &ub);
+    let event = graph.collect_delta_and_pop(None, &mut deltas);
+    assert_eq!(event, Some((on0, oe1)));
+    let mut deltas = Deltas::default();
+    let event = graph.collect_delta_and_pop(None, &mut deltas);
+    assert_eq!(event, Some((on1, oe1)));
+    assert_eq!(deltas.d1, vec![(1, ub)]);
+}
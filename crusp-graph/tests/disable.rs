@@ -0,0 +1,74 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let oe2 = FdEvent::FIX;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe2, &in1, &ie1, 1i64);
+    graph.add_event(&on1, &oe2, &in2, &ie2, 1i64);
+    let mut graph = graph.finalize();
+    assert!(!graph.is_disabled(&on1));
+
+    // disabled before collect
+    graph.disable(&on1);
+    assert!(graph.is_disabled(&on1));
+    graph.notify(&in1, &ie1);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on0, oe1)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    // disabled while in the queue
+    graph.enable(&on1);
+    assert!(!graph.is_disabled(&on1));
+    graph.notify(&in2, &ie2);
+    graph.collect(None);
+    graph.disable(&on1);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    // enabled again, no stale event
+    graph.enable(&on1);
+    graph.notify(&in1, &ie1);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on1, oe2)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on0, oe1)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    // output nodes without links yet
+    let on5 = Prop(5);
+    assert!(!graph.is_disabled(&on5));
+    graph.disable(&on5);
+    assert!(graph.is_disabled(&on5));
+    graph.enable(&on5);
+    assert!(!graph.is_disabled(&on5));
+}
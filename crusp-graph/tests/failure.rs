@@ -0,0 +1,67 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let in10 = Var(0);
+    let in11 = Var(1);
+    let ie1 = FdEvent::BOUNDS;
+    let in20 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in10, &ie1, 0i64);
+    graph.add_event(&on0, &oe1, &in20, &ie2, 0i64);
+    graph.add_event(&on1, &oe1, &in10, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in11, &ie1, 0i64);
+    let mut graph = graph.finalize();
+    assert_eq!(graph.weight(&on0), 0);
+    assert_eq!(graph.weighted_degree(&in10), 0);
+
+    graph.notify_failure(&on0);
+    assert_eq!(graph.weight(&on0), 1);
+    assert_eq!(graph.weight(&on1), 0);
+    assert_eq!(graph.weighted_degree(&in10), 1);
+    assert_eq!(graph.weighted_degree(&in11), 0);
+    assert_eq!(graph.weighted_degree(&in20), 1);
+
+    graph.notify_failure(&on1);
+    graph.notify_failure(&on1);
+    assert_eq!(graph.weight(&on1), 2);
+    assert_eq!(graph.weighted_degree(&in10), 3);
+    assert_eq!(graph.weighted_degree(&in11), 2);
+    assert_eq!(graph.weighted_degree(&in20), 1);
+
+    // weights survive backtracking
+    graph.push_level();
+    graph.notify_failure(&on0);
+    graph.pop_level();
+    assert_eq!(graph.weight(&on0), 2);
+    assert_eq!(graph.weighted_degree(&in20), 2);
+
+    // output nodes without links yet
+    let on5 = Prop(5);
+    assert_eq!(graph.weight(&on5), 0);
+    graph.notify_failure(&on5);
+    assert_eq!(graph.weight(&on5), 1);
+}
@@ -0,0 +1,73 @@
+use crusp_core::{Mergeable, Nullable, Subsumed};
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+}
+
+const DOMAIN: usize = 0;
+const BOUNDS: usize = 1;
+const LB: usize = 2;
+const UB: usize = 3;
+const FIX: usize = 4;
+
+// propagators woken by `event`
+fn woken(graph: &mut GraphName, event: FdEvent) -> Vec<usize> {
+    graph.notify(&Var(0), &event);
+    let mut props = Vec::new();
+    while let Some((prop, _)) = graph.collect_and_pop(None) {
+        props.push(prop.0);
+    }
+    props.sort_unstable();
+    props
+}
+
+pub fn main() {
+    assert!(FdEvent::null().is_null());
+    assert!(FdEvent::FIX.implies(FdEvent::LB));
+    assert!(FdEvent::FIX.implies(FdEvent::UB));
+    assert!(FdEvent::LB.implies(FdEvent::BOUNDS));
+    assert!(FdEvent::UB.implies(FdEvent::BOUNDS));
+    assert!(FdEvent::BOUNDS.implies(FdEvent::DOMAIN));
+    assert!(!FdEvent::DOMAIN.implies(FdEvent::BOUNDS));
+    assert!(!FdEvent::LB.implies(FdEvent::UB));
+    let both = FdEvent::LB.merge(FdEvent::UB);
+    assert!(both.implies(FdEvent::LB) && both.implies(FdEvent::UB));
+    assert_ne!(both, FdEvent::FIX);
+    assert_eq!(FdEvent::DOMAIN.merge(FdEvent::FIX), FdEvent::FIX);
+    assert!(FdEvent::FIX.is_subsumed_under(&FdEvent::BOUNDS));
+    assert!(!FdEvent::DOMAIN.is_subsumed_under(&FdEvent::BOUNDS));
+
+    let mut graph = GraphName::builder();
+    let subscriptions = [
+        (DOMAIN, FdEvent::DOMAIN),
+        (BOUNDS, FdEvent::BOUNDS),
+        (LB, FdEvent::LB),
+        (UB, FdEvent::UB),
+        (FIX, FdEvent::FIX),
+    ];
+    for &(prop, event) in subscriptions.iter() {
+        graph.add_event(&Prop(prop), &event, &Var(0), &event, 0i64);
+    }
+    let mut graph = graph.finalize();
+
+    // removing an inner value only wakes the domain propagator
+    assert_eq!(woken(&mut graph, FdEvent::DOMAIN), vec![DOMAIN]);
+    assert_eq!(woken(&mut graph, FdEvent::BOUNDS), vec![DOMAIN, BOUNDS]);
+    assert_eq!(woken(&mut graph, FdEvent::LB), vec![DOMAIN, BOUNDS, LB]);
+    assert_eq!(woken(&mut graph, FdEvent::UB), vec![DOMAIN, BOUNDS, UB]);
+    assert_eq!(woken(&mut graph, both), vec![DOMAIN, BOUNDS, LB, UB]);
+    // fixing a variable wakes every propagator
+    assert_eq!(woken(&mut graph, FdEvent::FIX), vec![DOMAIN, BOUNDS, LB, UB, FIX]);
+}
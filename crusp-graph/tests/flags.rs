@@ -0,0 +1,61 @@
+use crusp_core::{Mergeable, Nullable, Subsumed};
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FlagEvent<u8>),
+    #[input]
+    vars: (Var, FlagEvent<u8>),
+}
+
+fn pop_all(graph: &mut GraphName) -> Vec<usize> {
+    let mut props = Vec::new();
+    while let Some((prop, _)) = graph.collect_and_pop(None) {
+        props.push(prop.0);
+    }
+    props
+}
+
+pub fn main() {
+    let none = FlagEvent::<u8>::null();
+    let low = FlagEvent::new(0b01u8);
+    let high = FlagEvent::new(0b10u8);
+    let both = low.merge(high);
+    assert!(none.is_null());
+    assert_eq!(both.bits(), 0b11);
+    assert!(both.contains(low));
+    assert!(both.is_subsumed_under(&low));
+    assert!(low.is_subsumed_under(&none));
+    assert!(!low.is_subsumed_under(&both));
+    assert!(!low.is_subsumed_under(&high));
+    let mut event = both;
+    assert_eq!(event.nullify(), both);
+    assert_eq!(event, FlagEvent::default());
+
+    let var = Var(0);
+    let mut graph = GraphName::builder();
+    graph.add_event(&Prop(0), &low, &var, &low, 0i64);
+    graph.add_event(&Prop(1), &both, &var, &both, 1i64);
+    graph.add_event(&Prop(2), &high, &var, &high, 2i64);
+    let mut graph = graph.finalize();
+
+    // links are only triggered by the events carrying all their flags
+    graph.notify(&var, &low);
+    assert_eq!(pop_all(&mut graph), vec![0]);
+    graph.notify(&var, &high);
+    assert_eq!(pop_all(&mut graph), vec![2]);
+    graph.notify(&var, &both);
+    assert_eq!(pop_all(&mut graph), vec![2, 1, 0]);
+    // the pending events of a node are merged
+    graph.notify(&var, &low);
+    graph.notify(&var, &high);
+    assert_eq!(pop_all(&mut graph), vec![2, 1, 0]);
+}
@@ -0,0 +1,281 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+use crusp_core::{Nullable, Mergeable, Subsumed};
+use std::fmt::Debug;
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct OutNode {
+    idx: usize,
+}
+impl GraphNode for OutNode {}
+
+
+impl From<OutNode> for usize {
+    fn from(idx: OutNode) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for OutNode {
+    fn from(idx: usize) -> Self {
+        OutNode{idx}
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct OutEvent {
+    val: i32,
+}
+impl Nullable for OutEvent {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        OutEvent { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for OutEvent {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        OutEvent { val: ret }
+    }
+}
+impl Subsumed for OutEvent {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl GraphEvent for OutEvent {}
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct InNode1 {
+    idx: usize,
+}
+impl GraphNode for InNode1 {}
+
+
+impl From<InNode1> for usize {
+    fn from(idx: InNode1) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for InNode1 {
+    fn from(idx: usize) -> Self {
+        InNode1{idx}
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct InEvent1 {
+    val: i32,
+}
+impl Nullable for InEvent1 {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        InEvent1 { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for InEvent1 {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        InEvent1 { val: ret }
+    }
+}
+impl Subsumed for InEvent1 {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl GraphEvent for InEvent1 {}
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct InNode2 {
+    idx: usize,
+}
+impl GraphNode for InNode2 {}
+impl From<InNode2> for usize {
+    fn from(idx: InNode2) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for InNode2 {
+    fn from(idx: usize) -> Self {
+        InNode2{idx}
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct InEvent2 {
+    val: i32,
+}
+impl Nullable for InEvent2 {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        InEvent2 { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for InEvent2 {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        InEvent2 { val: ret }
+    }
+}
+impl Subsumed for InEvent2 {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl GraphEvent for InEvent2 {}
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+// the input node type may be any node type, the output one included
+#[crusp_lazy_graph]
+struct NodeGraph<V: GraphNode, E: GraphEvent> {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (V, E),
+}
+
+// the generic parameters may have the names of the trait parameters
+#[crusp_lazy_graph]
+struct NamesGraph<Visitor: GraphEvent, Look: GraphEvent> {
+    #[output]
+    out: (OutNode, Visitor),
+    #[input]
+    in1: (InNode1, Look),
+}
+
+// the input node types must stay distinct, otherwise the per node impls could overlap
+#[crusp_lazy_graph]
+struct GenericGraph<OE: GraphEvent, E1, E2>
+where
+    E1: GraphEvent,
+    E2: GraphEvent,
+{
+    #[output]
+    out: (OutNode, OE),
+    #[input]
+    in1: (InNode1, E1),
+    #[input]
+    in2: (InNode2, E2),
+}
+
+#[crusp_lazy_graph]
+struct LifetimeGraph<'a, E: GraphEvent> {
+    #[output]
+    out: (OutNode, OutEvent),
+    #[input]
+    in1: (InNode1, E),
+}
+
+struct Counter(usize);
+
+impl<Node: GraphNode> VisitMut<Node> for Counter {
+    fn visit_mut(&mut self, _node: &Node) {
+        self.0 += 1;
+    }
+}
+
+pub fn main() {
+    let oe1 = OutEvent { val: 1 };
+    let on0 = OutNode { idx: 0 };
+    let on1 = OutNode { idx: 1 };
+    let in1 = InNode1 { idx: 0 };
+    let ie1 = InEvent1 { val: 1 };
+    let in2 = InNode2 { idx: 0 };
+    let ie2 = InEvent2 { val: 1 };
+
+    let mut graph = GenericGraph::<OutEvent, InEvent1, InEvent2>::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in2, &ie2, 1i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    graph.notify(&in2, &ie2);
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+    let mut counter = Counter(0);
+    graph.visit_all_in_nodes(&on1, &mut counter);
+    assert_eq!(counter.0, 1);
+
+    // same definition with the input events swapped
+    let mut graph = GenericGraph::<OutEvent, InEvent2, InEvent1>::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie2, 0i64);
+    graph.add_event(&on1, &oe1, &in2, &ie1, 1i64);
+    let mut graph = graph.finalize();
+    graph.push_level();
+    graph.notify(&in1, &ie2);
+    graph.pop_level();
+    assert_eq!(graph.collect_and_pop(None), None);
+    graph.notify(&in2, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+
+    let mut graph = NodeGraph::<InNode1, InEvent1>::builder();
+    graph.add_event(&Prop(0), &FdEvent::FIX, &in1, &ie1, 0i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((Prop(0), FdEvent::FIX)));
+    graph.notify_failure(&Prop(0));
+    assert_eq!(graph.weight(&Prop(0)), 1);
+    assert_eq!(graph.weighted_degree(&in1), 1);
+
+    // propagators woken by propagators
+    let mut graph = NodeGraph::<Prop, FdEvent>::builder();
+    graph.add_event(&Prop(1), &FdEvent::FIX, &Prop(0), &FdEvent::DOMAIN, 0i64);
+    let mut graph = graph.finalize();
+    graph.notify(&Prop(0), &FdEvent::BOUNDS);
+    assert_eq!(graph.collect_and_pop(None), Some((Prop(1), FdEvent::FIX)));
+    graph.notify_failure(&Prop(1));
+    assert_eq!(graph.weight(&Prop(0)), 0);
+    assert_eq!(graph.weight(&Prop(1)), 1);
+    assert_eq!(graph.weighted_degree(&Prop(0)), 1);
+    assert_eq!(graph.weighted_degree(&Prop(1)), 0);
+    let mut counter = Counter(0);
+    graph.visit_all_in_nodes(&Prop(1), &mut counter);
+    assert_eq!(counter.0, 1);
+    assert_eq!(graph.vars(&Prop(1)).copied().collect::<Vec<_>>(), vec![Prop(0)]);
+
+    let mut graph = NamesGraph::<OutEvent, InEvent1>::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    let mut counter = Counter(0);
+    graph.visit_all_in_nodes(&on0, &mut counter);
+    assert_eq!(counter.0, 1);
+
+    let mut graph = LifetimeGraph::<'static, InEvent1>::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+}
@@ -0,0 +1,167 @@
+use crusp_core::{Mergeable, Nullable, Subsumed};
+use crusp_graph::{
+    InOutEventHandlerBuilder, InputEventHandler, OutputEventHandler, OutputEventHandlerLookup,
+    VisitAllOutputsNode,
+};
+use crusp_graph_derive::crusp_lazy_graph;
+
+// the generated code must not pick up these names
+#[allow(dead_code)]
+struct OutNode;
+#[allow(dead_code)]
+trait LookEvent {}
+#[allow(dead_code)]
+trait VisitMut {}
+#[allow(dead_code)]
+trait GraphBuilder {}
+#[allow(dead_code)]
+trait VisitOutputsNode {}
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct Propagator {
+    idx: usize,
+}
+impl crusp_graph::GraphNode for Propagator {}
+
+impl From<Propagator> for usize {
+    fn from(idx: Propagator) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for Propagator {
+    fn from(idx: usize) -> Self {
+        Propagator { idx }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PropEvent {
+    val: i32,
+}
+impl Nullable for PropEvent {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        PropEvent { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for PropEvent {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        PropEvent { val: ret }
+    }
+}
+impl Subsumed for PropEvent {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl crusp_graph::GraphEvent for PropEvent {}
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct Var {
+    idx: usize,
+}
+impl crusp_graph::GraphNode for Var {}
+
+impl From<Var> for usize {
+    fn from(idx: Var) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for Var {
+    fn from(idx: usize) -> Self {
+        Var { idx }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct VarEvent {
+    val: i32,
+}
+impl Nullable for VarEvent {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        VarEvent { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for VarEvent {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        VarEvent { val: ret }
+    }
+}
+impl Subsumed for VarEvent {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl crusp_graph::GraphEvent for VarEvent {}
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Propagator, PropEvent),
+    #[input]
+    vars: (Var, VarEvent),
+}
+
+struct Counter {
+    count: usize,
+}
+
+impl crusp_graph::VisitMut<Var> for Counter {
+    fn visit_mut(&mut self, _var: &Var) {
+        self.count += 1;
+    }
+}
+
+impl crusp_graph::LookEvent<Var, VarEvent> for Counter {
+    fn look_event(&mut self, _var: &Var, _event: &VarEvent) {
+        self.count += 1;
+    }
+}
+
+pub fn main() {
+    let pe1 = PropEvent { val: 1 };
+    let p0 = Propagator { idx: 0 };
+    let v0 = Var { idx: 0 };
+    let v1 = Var { idx: 1 };
+    let ve1 = VarEvent { val: 1 };
+    let mut graph = GraphName::builder();
+    graph.add_event(&p0, &pe1, &v0, &ve1, 0i64);
+    graph.add_event(&p0, &pe1, &v1, &ve1, 0i64);
+    let mut graph = graph.finalize();
+
+    let mut counter = Counter { count: 0 };
+    graph.visit_all_in_nodes(&p0, &mut counter);
+    assert_eq!(counter.count, 2);
+
+    graph.notify(&v0, &ve1);
+    assert_eq!(graph.collect_and_pop(None), Some((p0, pe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+
+    let mut counter = Counter { count: 0 };
+    graph.notify(&v1, &ve1);
+    assert_eq!(graph.collect_look_and_pop(&mut counter, None), Some((p0, pe1)));
+    assert_eq!(counter.count, 1);
+}
@@ -0,0 +1,89 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let on2 = Prop(2);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 2i64);
+    graph.add_event(&on1, &oe1, &in1, &ie1, 1i64);
+    graph.add_event(&on2, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on2, &oe1, &in2, &ie2, 0i64);
+    graph.set_idempotent(&on0, true);
+    assert!(graph.is_idempotent(&on0));
+    let mut graph = graph.finalize();
+    assert!(graph.is_idempotent(&on0));
+    assert!(!graph.is_idempotent(&on1));
+
+    // the running idempotent node does not wake itself up
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+
+    // the running node is not idempotent
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+
+    // idempotence changed during search
+    graph.set_idempotent(&on0, false);
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    graph.notify(&in2, &ie2);
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+
+    // group of ignored nodes, only for this collect
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop_ignoring(&[on0, on1]), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop_ignoring(&[on0, on1]), None);
+    graph.notify(&in1, &ie1);
+    graph.collect_ignoring(&[on2]);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+
+    // output nodes without links yet
+    let on7 = Prop(7);
+    assert!(!graph.is_idempotent(&on7));
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop_ignoring(&[on7, on0]), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+}
@@ -0,0 +1,67 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Advisor(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[output]
+    advisors: (Advisor, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FdEvent),
+}
+
+fn idxs<'a, Node: GraphNode + 'a>(nodes: impl Iterator<Item = &'a Node>) -> Vec<usize> {
+    nodes.map(|&node| node.into()).collect()
+}
+
+pub fn main() {
+    let mut graph = GraphName::builder();
+    for &idx in [3, 0, 2, 0].iter() {
+        graph.add_event(&Prop(0), &FdEvent::FIX, &Var(idx), &FdEvent::BOUNDS, 0i64);
+    }
+    graph.add_event(&Prop(0), &FdEvent::FIX, &BoolVar(1), &FdEvent::FIX, 0i64);
+    graph.add_event(&Prop(1), &FdEvent::FIX, &BoolVar(4), &FdEvent::FIX, 0i64);
+    graph.add_event(&Advisor(0), &FdEvent::DOMAIN, &Var(5), &FdEvent::DOMAIN, 0i64);
+    let mut graph = graph.finalize();
+
+    // each input has its own method, the output is given by the node type
+    assert_eq!(idxs(graph.vars(&Prop(0))), vec![0, 2, 3]);
+    assert_eq!(graph.bools(&Prop(0)).count(), 1);
+    assert_eq!(graph.vars(&Prop(1)).count(), 0);
+    assert!(graph.bools(&Prop(1)).any(|&node| node == BoolVar(4)));
+    assert_eq!(graph.vars(&Advisor(0)).next(), Some(&Var(5)));
+    assert_eq!(graph.bools(&Advisor(0)).next(), None);
+    assert_eq!(graph.vars(&Prop(0)).filter(|&&node| node > Var(1)).count(), 2);
+    assert_eq!(InNodes::<Prop, BoolVar>::in_nodes(&graph, &Prop(1)).len(), 1);
+    assert_eq!(graph.vars(&Prop(9)).count(), 0);
+
+    // links inserted in the finalized graph are seen, in order
+    graph.add_event(&Prop(1), &FdEvent::FIX, &Var(1), &FdEvent::BOUNDS, 0i64);
+    graph.add_event(&Prop(0), &FdEvent::FIX, &Var(1), &FdEvent::BOUNDS, 0i64);
+    graph.add_event(&Prop(0), &FdEvent::FIX, &Var(4), &FdEvent::BOUNDS, 0i64);
+    assert_eq!(idxs(graph.vars(&Prop(1))), vec![1]);
+    assert_eq!(idxs(graph.vars(&Prop(0))), vec![0, 1, 2, 3, 4]);
+    assert_eq!(idxs(graph.vars(&Prop(0)).rev()), vec![4, 3, 2, 1, 0]);
+    assert_eq!(graph.vars(&Prop(0)).len(), 5);
+
+    let mut builder = AdjacentListGraph::<Prop, Var>::builder();
+    builder.add_node(&Prop(0), &Var(2));
+    builder.add_node(&Prop(0), &Var(1));
+    let rev = builder.finalize();
+    assert_eq!(idxs(rev.in_nodes(&Prop(0)).rev()), vec![2, 1]);
+}
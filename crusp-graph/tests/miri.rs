@@ -0,0 +1,147 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Advisor(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output(rank = 1)]
+    props: (Prop, FdEvent),
+    #[output]
+    advisors: (Advisor, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+#[derive(Default)]
+struct Deltas {
+    d1: Vec<usize>,
+    d2: Vec<usize>,
+}
+
+impl VisitMut<(Var, FdEvent)> for Deltas {
+    fn visit_mut(&mut self, t: &(Var, FdEvent)) {
+        self.d1.push(t.0.into());
+    }
+}
+
+impl VisitMut<(BoolVar, FlagEvent<u8>)> for Deltas {
+    fn visit_mut(&mut self, t: &(BoolVar, FlagEvent<u8>)) {
+        self.d2.push(t.0.into());
+    }
+}
+
+impl LookEvent<Var, FdEvent> for Deltas {
+    fn look_event(&mut self, node: &Var, _event: &FdEvent) {
+        self.d1.push(node.0);
+    }
+}
+
+impl LookEvent<BoolVar, FlagEvent<u8>> for Deltas {
+    fn look_event(&mut self, node: &BoolVar, _event: &FlagEvent<u8>) {
+        self.d2.push(node.0);
+    }
+}
+
+// props 0 and 1 and advisor 0 watch the first inputs, prop 1 and advisor 1 the second ones
+fn graph() -> GraphName {
+    let oe1 = FdEvent::BOUNDS;
+    let ae1 = FdEvent::DOMAIN;
+    let ie1 = FdEvent::BOUNDS;
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    for idx in 0..2 {
+        graph.add_event(&Prop(0), &oe1, &Var(idx), &ie1, 0i64);
+        graph.add_event(&Prop(1), &oe1, &Var(idx), &ie1, 1i64);
+        graph.add_event(&Prop(1), &oe1, &BoolVar(idx), &ie2, 1i64);
+        graph.add_event(&Advisor(0), &ae1, &Var(idx), &ie1, 0i64);
+        graph.add_event(&Advisor(1), &ae1, &BoolVar(idx), &ie2, 0i64);
+    }
+    graph.finalize()
+}
+
+fn pop_props(graph: &mut GraphName) -> Vec<usize> {
+    let mut props = Vec::new();
+    while let Some((node, _)) = OutputEventHandler::<Prop, FdEvent>::collect_and_pop(graph, None) {
+        props.push(node.0);
+    }
+    props
+}
+
+#[test]
+fn split_in_out() {
+    let mut graph = graph();
+    graph.notify(&Var(0), &FdEvent::BOUNDS);
+    let (props, advisors, in1, in2) = graph.split_in_out();
+    in1.trigger_events(|link| match link {
+        GraphNameLink::Props(out) => props.collect_out_event(out, None),
+        GraphNameLink::Advisors(out) => advisors.collect_out_event(out, None),
+    });
+    in2.trigger_events(|_| unreachable!());
+    assert_eq!(props.pop().map(|(node, _)| node.0), Some(1));
+    assert_eq!(advisors.pop().map(|(node, _)| node.0), Some(0));
+}
+
+#[test]
+fn collect_and_pop() {
+    let mut graph = graph();
+    graph.notify(&Var(1), &FdEvent::BOUNDS);
+    graph.notify(&BoolVar(0), &FlagEvent::new(0b01u8));
+    assert_eq!(pop_props(&mut graph), vec![1, 0]);
+    let mut advisors = Vec::new();
+    while let Some(output) = graph.collect_and_pop_any() {
+        match output {
+            GraphNameOutput::Advisors(node, event) => {
+                assert!(!event.is_null());
+                advisors.push(node.0);
+            }
+            GraphNameOutput::Props(node, event) => unreachable!("{:?} {:?}", node, event),
+        }
+    }
+    advisors.sort_unstable();
+    assert_eq!(advisors, vec![0, 1]);
+}
+
+#[test]
+fn collect_delta_and_look() {
+    let mut graph = graph();
+    graph.notify(&Var(0), &FdEvent::BOUNDS);
+    graph.notify(&BoolVar(1), &FlagEvent::new(0b01u8));
+    let mut deltas = Deltas::default();
+    let popped = OutputDeltaEventHandler::<Prop, FdEvent, _>::collect_delta_and_pop(&mut graph, None, &mut deltas);
+    assert_eq!(popped.map(|(node, _)| node.0), Some(1));
+    assert_eq!((deltas.d1, deltas.d2), (vec![0], vec![1]));
+
+    graph.notify(&Var(1), &FdEvent::BOUNDS);
+    let mut looked = Deltas::default();
+    let popped = OutputEventHandlerLookup::<Advisor, FdEvent, _>::collect_look_and_pop(&mut graph, &mut looked, None);
+    assert!(popped.is_some());
+    assert_eq!(looked.d1, vec![1]);
+}
+
+#[test]
+fn backtrack_and_disable() {
+    let mut graph = graph();
+    graph.push_level();
+    graph.disable(&Prop(1));
+    graph.notify(&BoolVar(0), &FlagEvent::new(0b01u8));
+    graph.notify(&Var(0), &FdEvent::BOUNDS);
+    assert_eq!(pop_props(&mut graph), vec![0]);
+    graph.notify_failure(&Prop(0));
+    graph.pop_level();
+    assert_eq!(graph.weighted_degree(&Var(0)), 1);
+    graph.notify(&BoolVar(0), &FlagEvent::new(0b01u8));
+    assert_eq!(pop_props(&mut graph), vec![1]);
+}
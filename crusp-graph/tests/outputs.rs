@@ -0,0 +1,140 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Advisor(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct RankedGraph {
+    #[output(rank = 1)]
+    props: (Prop, FdEvent),
+    #[output(rank = 0, scheduler = FifoScheduler<Advisor>)]
+    advisors: (Advisor, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+#[crusp_lazy_graph]
+struct CostGraph {
+    #[output]
+    props: (Prop, FdEvent),
+    #[output]
+    advisors: (Advisor, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+}
+
+fn popped(output: Option<RankedGraphOutput>) -> Option<(&'static str, usize)> {
+    output.map(|output| match output {
+        RankedGraphOutput::Props(node, event) => {
+            assert!(!event.is_null());
+            ("prop", node.0)
+        }
+        RankedGraphOutput::Advisors(node, event) => {
+            assert!(!event.is_null());
+            ("advisor", node.0)
+        }
+    })
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let ae1 = FdEvent::DOMAIN;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let an0 = Advisor(0);
+    let an1 = Advisor(1);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+
+    // the advisors are ranked first, in their own scheduling order
+    let mut graph = RankedGraph::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in2, &ie2, 3i64);
+    graph.add_event(&an0, &ae1, &in1, &ie1, 1i64);
+    graph.add_event(&an1, &ae1, &in1, &ie1, 5i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    graph.notify(&in2, &ie2);
+    assert_eq!(popped(graph.collect_and_pop_any()), Some(("advisor", 0)));
+    assert_eq!(popped(graph.collect_and_pop_any()), Some(("advisor", 1)));
+    assert_eq!(popped(graph.collect_and_pop_any()), Some(("prop", 1)));
+    assert_eq!(popped(graph.collect_and_pop_any()), Some(("prop", 0)));
+    assert_eq!(popped(graph.collect_and_pop_any()), None);
+
+    // each output can still be popped on its own
+    graph.notify(&in1, &ie1);
+    assert_eq!(
+        OutputEventHandler::<Prop, FdEvent>::collect_and_pop(&mut graph, None),
+        Some((on0, oe1))
+    );
+    assert_eq!(OutputEventHandler::<Prop, FdEvent>::collect_and_pop(&mut graph, None), None);
+    assert_eq!(
+        OutputEventHandler::<Advisor, FdEvent>::collect_and_pop(&mut graph, None),
+        Some((an0, ae1))
+    );
+
+    // backtracking restores every output
+    graph.push_level();
+    graph.notify(&in2, &ie2);
+    graph.collect_all();
+    graph.pop_level();
+    assert_eq!(popped(graph.pop_any()), Some(("advisor", 1)));
+    assert_eq!(popped(graph.collect_and_pop_any()), None);
+
+    // outputs of the same rank are ordered by cost
+    let mut graph = CostGraph::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 2i64);
+    graph.add_event(&an0, &ae1, &in1, &ie1, 4i64);
+    graph.add_event(&an1, &ae1, &in1, &ie1, 1i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    let mut order = Vec::new();
+    while let Some(output) = graph.collect_and_pop_any() {
+        order.push(match output {
+            CostGraphOutput::Props(node, event) => {
+                assert!(!event.is_null());
+                ("prop", node.0)
+            }
+            CostGraphOutput::Advisors(node, event) => {
+                assert!(!event.is_null());
+                ("advisor", node.0)
+            }
+        });
+    }
+    assert_eq!(order, vec![("advisor", 0), ("prop", 0), ("advisor", 1)]);
+
+    // an idempotent node stops running when another output pops
+    let mut graph = CostGraph::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&an0, &ae1, &in1, &ie1, 0i64);
+    graph.set_idempotent(&on0, true);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    assert_eq!(
+        OutputEventHandler::<Prop, FdEvent>::collect_and_pop(&mut graph, None),
+        Some((on0, oe1))
+    );
+    assert_eq!(
+        OutputEventHandler::<Advisor, FdEvent>::collect_and_pop(&mut graph, None),
+        Some((an0, ae1))
+    );
+    graph.notify(&in1, &ie1);
+    assert_eq!(
+        OutputEventHandler::<Prop, FdEvent>::collect_and_pop(&mut graph, None),
+        Some((on0, oe1))
+    );
+}
@@ -2,4 +2,25 @@
 fn tests() {
     let t = trybuild::TestCases::new();
     t.pass("tests/simple-derive.rs");
+    t.pass("tests/runtime-insert.rs");
+    t.pass("tests/disable.rs");
+    t.pass("tests/backtrack.rs");
+    t.pass("tests/failure.rs");
+    t.pass("tests/scoring.rs");
+    t.pass("tests/changes.rs");
+    t.pass("tests/delta.rs");
+    t.pass("tests/scheduler.rs");
+    t.pass("tests/idempotent.rs");
+    t.pass("tests/generics.rs");
+    t.pass("tests/visibility.rs");
+    t.pass("tests/qualified.rs");
+    t.pass("tests/outputs.rs");
+    t.pass("tests/hygiene.rs");
+    t.pass("tests/flags.rs");
+    t.pass("tests/fd-events.rs");
+    t.pass("tests/adjacency.rs");
+    t.pass("tests/iterators.rs");
+    t.pass("tests/subscribers.rs");
+    t.pass("tests/stats.rs");
+    t.compile_fail("tests/ui/*.rs");
 }
@@ -0,0 +1,134 @@
+use crusp_graph::*;
+use crusp_graph_derive::crusp_lazy_graph;
+use crusp_core::{ConstraintId, VariableId};
+
+mod vars {
+    use crusp_graph::GraphNode;
+
+    #[derive(
+        PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+    )]
+    pub struct VarId {
+        pub idx: usize,
+    }
+    impl GraphNode for VarId {}
+
+    impl From<VarId> for usize {
+        fn from(idx: VarId) -> Self {
+            idx.idx
+        }
+    }
+
+    impl From<usize> for VarId {
+        fn from(idx: usize) -> Self {
+            VarId{idx}
+        }
+    }
+}
+
+mod events {
+    use crusp_core::{Nullable, Mergeable, Subsumed};
+    use crusp_graph::GraphEvent;
+    use std::fmt::Debug;
+    use std::marker::PhantomData;
+
+    #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+    pub struct DomEvent {
+        pub val: i32,
+    }
+    impl Nullable for DomEvent {
+        fn is_null(&self) -> bool {
+            self.val == 0
+        }
+        fn null() -> Self {
+            DomEvent { val: 0 }
+        }
+        fn nullify(&mut self) -> Self {
+            let prev = *self;
+            *self = Self::null();
+            prev
+        }
+    }
+    impl Mergeable for DomEvent {
+        fn merge(&self, rhs: Self) -> Self {
+            let ret = self.val | rhs.val;
+            DomEvent { val: ret }
+        }
+    }
+    impl Subsumed for DomEvent {
+        fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+            true
+        }
+    }
+    impl GraphEvent for DomEvent {}
+
+    #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+    pub struct Tagged<Tag> {
+        pub val: i32,
+        pub tag: PhantomData<Tag>,
+    }
+    impl<Tag: Copy + Debug> Nullable for Tagged<Tag> {
+        fn is_null(&self) -> bool {
+            self.val == 0
+        }
+        fn null() -> Self {
+            Tagged { val: 0, tag: PhantomData }
+        }
+        fn nullify(&mut self) -> Self {
+            let prev = *self;
+            *self = Self::null();
+            prev
+        }
+    }
+    impl<Tag: Copy + Debug> Mergeable for Tagged<Tag> {
+        fn merge(&self, rhs: Self) -> Self {
+            let ret = self.val | rhs.val;
+            Tagged { val: ret, tag: PhantomData }
+        }
+    }
+    impl<Tag: Copy + Debug> Subsumed for Tagged<Tag> {
+        fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+            true
+        }
+    }
+    impl<Tag: Copy + Debug> GraphEvent for Tagged<Tag> {}
+}
+
+pub trait Domain {
+    type Event;
+}
+
+pub struct Fd;
+
+impl Domain for Fd {
+    type Event = events::DomEvent;
+}
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    out: (crusp_core::ConstraintId, crate::events::DomEvent),
+    #[input]
+    in1: (crate::vars::VarId, <Fd as Domain>::Event),
+    #[input]
+    in2: (::crusp_core::VariableId, events::Tagged<u8>),
+}
+
+pub fn main() {
+    let oe1 = events::DomEvent { val: 1 };
+    let on0 = ConstraintId::from(0usize);
+    let on1 = ConstraintId::from(1usize);
+    let in1 = vars::VarId { idx: 0 };
+    let ie1 = events::DomEvent { val: 1 };
+    let in2 = VariableId::from(0usize);
+    let ie2 = events::Tagged::<u8> { val: 1, tag: std::marker::PhantomData };
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in2, &ie2, 1i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in1, &ie1);
+    graph.notify(&in2, &ie2);
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+}
@@ -0,0 +1,92 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+#[derive(Debug)]
+pub struct MyVisitor {
+    pub n1: usize,
+    pub n2: usize,
+}
+
+impl VisitMut<Var> for MyVisitor {
+    fn visit_mut(&mut self, _t: &Var) {
+        self.n1 += 1;
+    }
+}
+
+impl VisitMut<BoolVar> for MyVisitor {
+    fn visit_mut(&mut self, _t: &BoolVar) {
+        self.n2 += 1;
+    }
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let oe2 = FdEvent::FIX;
+    let on0 = Prop(0);
+    let on3 = Prop(3);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in13 = Var(3);
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    let mut graph = graph.finalize();
+
+    // new out node and new in node
+    graph.add_event(&on3, &oe2, &in13, &ie1, 1i64);
+    graph.add_event(&on3, &oe2, &in2, &ie2, 1i64);
+    // new link between existing nodes
+    graph.add_event(&on0, &oe2, &in2, &ie2, 0i64);
+
+    let mut visitor = MyVisitor { n1: 0, n2: 0 };
+    graph.visit_all_in_nodes(&on3, &mut visitor);
+    assert_eq!(visitor.n1, 1);
+    assert_eq!(visitor.n2, 1);
+    graph.visit_all_in_nodes(&on0, &mut visitor);
+    assert_eq!(visitor.n1, 2);
+    assert_eq!(visitor.n2, 2);
+
+    // the inserted links are filtered like the others
+    graph.notify(&in13, &FdEvent::DOMAIN);
+    graph.notify(&in2, &FlagEvent::new(0b10u8));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    graph.notify(&in13, &FdEvent::LB);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on3, oe2)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    graph.notify(&in2, &ie2);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on3, oe2)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on0, oe2)));
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, None);
+
+    graph.notify(&in1, &ie1);
+    let event = graph.collect_and_pop(None);
+    assert_eq!(event, Some((on0, oe1)));
+}
@@ -0,0 +1,173 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph(scheduler = FifoScheduler<Prop>)]
+struct FifoGraph {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+#[crusp_lazy_graph(scheduler = ::crusp_graph::MultiLevelScheduler<Prop, 3>)]
+struct LevelGraph {
+    #[output]
+    props: (Prop, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FlagEvent<u8>),
+}
+
+fn pop_all<Sched: Scheduler<Prop>>(outs: &mut HandlerOutput<Prop, FdEvent, Sched>) -> Vec<usize> {
+    let mut nodes = Vec::new();
+    while let Some((node, _)) = outs.pop() {
+        nodes.push(node.0);
+    }
+    nodes
+}
+
+fn schedule_all<Sched: Scheduler<Prop>>(outs: &mut HandlerOutput<Prop, FdEvent, Sched>) {
+    let oe1 = FdEvent::BOUNDS;
+    for &(idx, cost) in [(0, 0), (1, 2), (2, 1), (3, 2), (1, 0)].iter() {
+        outs.collect_out_event(&OutCostEventLink::new(Prop(idx), oe1, cost), None);
+    }
+}
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let on0 = Prop(0);
+    let on1 = Prop(1);
+    let on2 = Prop(2);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+
+    let mut builder = HandlerOutput::<Prop, FdEvent>::builder();
+    builder.add_node(Prop(3));
+    let mut outs = builder.finalize();
+    schedule_all(&mut outs);
+    assert_eq!(pop_all(&mut outs), vec![3, 2, 0, 1]);
+
+    let mut builder = HandlerOutput::<Prop, FdEvent>::builder().with_scheduler::<FifoScheduler<Prop>>();
+    builder.add_node(Prop(3));
+    let mut outs = builder.finalize();
+    schedule_all(&mut outs);
+    assert_eq!(pop_all(&mut outs), vec![0, 1, 2, 3]);
+
+    let mut builder = HandlerOutput::<Prop, FdEvent>::builder().with_scheduler::<LifoScheduler<Prop>>();
+    builder.add_node(Prop(3));
+    let mut outs = builder.finalize();
+    schedule_all(&mut outs);
+    assert_eq!(pop_all(&mut outs), vec![3, 2, 1, 0]);
+
+    let mut builder = HandlerOutput::<Prop, FdEvent>::builder().with_scheduler::<MultiLevelScheduler<Prop, 2>>();
+    builder.add_node(Prop(3));
+    let mut outs = builder.finalize();
+    schedule_all(&mut outs);
+    assert_eq!(pop_all(&mut outs), vec![1, 2, 3, 0]);
+
+    // backtracking restores the order of the queues
+    let mut builder = HandlerOutput::<Prop, FdEvent>::builder().with_scheduler::<FifoScheduler<Prop>>();
+    builder.add_node(Prop(3));
+    let mut outs = builder.finalize();
+    outs.collect_out_event(&OutCostEventLink::new(on2, oe1, 0), None);
+    outs.push_level();
+    schedule_all(&mut outs);
+    outs.pop();
+    outs.pop();
+    outs.pop_level();
+    schedule_all(&mut outs);
+    assert_eq!(pop_all(&mut outs), vec![2, 0, 1, 3]);
+
+    // cost classes: the cheapest class first, FIFO within a class
+    let mut sched = CostClassScheduler::<Prop>::default();
+    let classes = [
+        (0, CostClass::Global),
+        (1, CostClass::Linear),
+        (2, CostClass::Unary),
+        (3, CostClass::Linear),
+        (4, CostClass::Binary),
+        (5, CostClass::Unary),
+    ];
+    for &(idx, class) in classes.iter() {
+        assert_eq!(sched.schedule(Prop(idx), class.into()), None);
+    }
+    // scheduled again, the node keeps its class and position
+    assert_eq!(sched.schedule(on1, CostClass::Unary.into()), Some(i64::from(CostClass::Linear)));
+    assert_eq!(sched.len(), 6);
+    let mut popped = Vec::new();
+    while let Some((node, _)) = sched.pop() {
+        popped.push(node.0);
+    }
+    assert_eq!(popped, vec![2, 5, 4, 1, 3, 0]);
+    assert!(sched.is_empty());
+    assert_eq!(sched.peek(), None);
+
+    // emptied classes are skipped
+    sched.schedule(on0, CostClass::Global.into());
+    sched.schedule(on1, CostClass::Unary.into());
+    assert_eq!(sched.pop(), Some((on1, i64::from(CostClass::Unary))));
+    sched.schedule(on2, CostClass::Linear.into());
+    assert_eq!(sched.peek(), Some((on2, i64::from(CostClass::Linear))));
+    assert_eq!(sched.pop(), Some((on2, i64::from(CostClass::Linear))));
+    assert_eq!(sched.pop(), Some((on0, i64::from(CostClass::Global))));
+    assert_eq!(sched.pop(), None);
+    assert_eq!(sched.peek(), None);
+
+    // backtracking undoes the schedules and pops in each class
+    let mut builder = HandlerOutput::<Prop, FdEvent>::builder().with_scheduler::<CostClassScheduler<Prop>>();
+    builder.add_node(Prop(3));
+    let mut outs = builder.finalize();
+    let link = |idx, class: CostClass| OutCostEventLink::new(Prop(idx), oe1, class.into());
+    outs.collect_out_event(&link(0, CostClass::Global), None);
+    outs.collect_out_event(&link(1, CostClass::Linear), None);
+    outs.push_level();
+    outs.collect_out_event(&link(2, CostClass::Unary), None);
+    outs.collect_out_event(&link(3, CostClass::Linear), None);
+    assert_eq!(outs.pop(), Some((on2, oe1)));
+    assert_eq!(outs.pop(), Some((on1, oe1)));
+    outs.push_level();
+    assert_eq!(outs.pop(), Some((Prop(3), oe1)));
+    outs.pop_level();
+    assert_eq!(outs.peek(), Some((Prop(3), i64::from(CostClass::Linear))));
+    outs.pop_level();
+    assert_eq!(outs.peek(), Some((on1, i64::from(CostClass::Linear))));
+    assert_eq!(pop_all(&mut outs), vec![1, 0]);
+
+    let mut graph = FifoGraph::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in1, &ie1, 2i64);
+    graph.add_event(&on2, &oe1, &in2, &ie2, 1i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in2, &ie2);
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+
+    let mut graph = LevelGraph::builder();
+    graph.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in1, &ie1, 5i64);
+    graph.add_event(&on2, &oe1, &in2, &ie2, 2i64);
+    let mut graph = graph.finalize();
+    graph.notify(&in2, &ie2);
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on1, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on2, oe1)));
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+    assert_eq!(graph.collect_and_pop(None), None);
+}
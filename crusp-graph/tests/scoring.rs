@@ -0,0 +1,160 @@
+use crusp_graph::*;
+use crusp_graph_derive::crusp_lazy_graph;
+use crusp_core::{Nullable, Mergeable, Subsumed};
+use std::fmt::Debug;
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct OutNode {
+    idx: usize,
+}
+impl GraphNode for OutNode {}
+
+
+impl From<OutNode> for usize {
+    fn from(idx: OutNode) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for OutNode {
+    fn from(idx: usize) -> Self {
+        OutNode{idx}
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct OutEvent {
+    val: i32,
+}
+impl Nullable for OutEvent {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        OutEvent { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for OutEvent {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        OutEvent { val: ret }
+    }
+}
+impl Subsumed for OutEvent {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl GraphEvent for OutEvent {}
+
+#[derive(
+    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
+)]
+pub struct InNode1 {
+    idx: usize,
+}
+impl GraphNode for InNode1 {}
+
+
+impl From<InNode1> for usize {
+    fn from(idx: InNode1) -> Self {
+        idx.idx
+    }
+}
+
+impl From<usize> for InNode1 {
+    fn from(idx: usize) -> Self {
+        InNode1{idx}
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct InEvent1 {
+    val: i32,
+}
+impl Nullable for InEvent1 {
+    fn is_null(&self) -> bool {
+        self.val == 0
+    }
+    fn null() -> Self {
+        InEvent1 { val: 0 }
+    }
+    fn nullify(&mut self) -> Self {
+        let prev = *self;
+        *self = Self::null();
+        prev
+    }
+}
+impl Mergeable for InEvent1 {
+    fn merge(&self, rhs: Self) -> Self {
+        let ret = self.val | rhs.val;
+        InEvent1 { val: ret }
+    }
+}
+impl Subsumed for InEvent1 {
+    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+impl GraphEvent for InEvent1 {}
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    out: (OutNode, OutEvent),
+    #[input]
+    in1: (InNode1, InEvent1),
+}
+
+pub fn main() {
+    let oe1 = OutEvent { val: 1 };
+    let on0 = OutNode { idx: 0 };
+    let on1 = OutNode { idx: 1 };
+    let in0 = InNode1 { idx: 0 };
+    let in1 = InNode1 { idx: 1 };
+    let in2 = InNode1 { idx: 2 };
+    let ie1 = InEvent1 { val: 1 };
+    let mut graph = GraphName::builder();
+    graph.add_event(&on0, &oe1, &in0, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in1, &ie1, 0i64);
+    graph.add_event(&on1, &oe1, &in2, &ie1, 0i64);
+    let mut graph = graph.finalize();
+
+    let mut chb = ChbScores::new(3);
+    let mut activities = ActivityScores::new(3);
+    assert_eq!(chb.best(|_| false), Some(in0));
+    assert_eq!(activities.best(|_| false), Some(in0));
+
+    // in1 and in2 lead to a conflict
+    graph.notify(&in1, &ie1);
+    graph.notify(&in2, &ie1);
+    graph.collect_look(&mut chb, None);
+    graph.notify(&in1, &ie1);
+    graph.collect_look(&mut activities, None);
+    chb.conflict();
+    activities.conflict();
+    assert!(chb.score(&in1) > 0.0);
+    assert_eq!(chb.score(&in1), chb.score(&in2));
+    assert_eq!(chb.score(&in0), 0.0);
+    assert!(activities.activity(&in1) > 0.0);
+    assert_eq!(activities.activity(&in2), 0.0);
+    assert_eq!(chb.best(|_| false), Some(in1));
+    assert_eq!(chb.best(|node| *node == in1), Some(in2));
+    assert_eq!(activities.best(|_| false), Some(in1));
+
+    // in2 is involved in a successful propagation
+    graph.notify(&in2, &ie1);
+    graph.collect_look(&mut chb, None);
+    chb.fixpoint();
+    assert!(chb.score(&in2) > chb.score(&in1));
+    assert_eq!(chb.best(|_| false), Some(in2));
+    assert_eq!(chb.best(|node| *node != in0), Some(in0));
+    assert_eq!(chb.best(|_| true), None);
+}
@@ -1,160 +1,44 @@
 //extern crate constraint_derive;
 
 use crusp_graph::*;
-use crusp_graph_derive::crusp_lazy_graph;
-use crusp_core::{Nullable, Mergeable, Subsumed};
+use crusp_graph_derive::{crusp_lazy_graph, GraphEvent, GraphNode};
 use std::fmt::Debug;
 
-#[derive(
-    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
-)]
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
 pub struct OutNode {
     idx: usize,
 }
-impl GraphNode for OutNode {}
 
-
-impl From<OutNode> for usize {
-    fn from(idx: OutNode) -> Self {
-        idx.idx
-    }
-}
-
-impl From<usize> for OutNode {
-    fn from(idx: usize) -> Self {
-        OutNode{idx}
-    }
-}
-
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, GraphEvent)]
 pub struct OutEvent {
     val: i32,
 }
-impl Nullable for OutEvent {
-    fn is_null(&self) -> bool {
-        self.val == 0
-    }
-    fn null() -> Self {
-        OutEvent { val: 0 }
-    }
-    fn nullify(&mut self) -> Self {
-        let prev = *self;
-        *self = Self::null();
-        prev
-    }
-}
-impl Mergeable for OutEvent {
-    fn merge(&self, rhs: Self) -> Self {
-        let ret = self.val | rhs.val;
-        OutEvent { val: ret }
-    }
-}
-impl Subsumed for OutEvent {
-    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
-        true
-    }
-}
-impl GraphEvent for OutEvent {}
 
-#[derive(
-    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
-)]
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
 pub struct InNode1 {
     idx: usize,
 }
-impl GraphNode for InNode1 {}
-
-
-impl From<InNode1> for usize {
-    fn from(idx: InNode1) -> Self {
-        idx.idx
-    }
-}
 
-impl From<usize> for InNode1 {
-    fn from(idx: usize) -> Self {
-        InNode1{idx}
-    }
-}
-
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, GraphEvent)]
 pub struct InEvent1 {
     val: i32,
 }
-impl Nullable for InEvent1 {
-    fn is_null(&self) -> bool {
-        self.val == 0
-    }
-    fn null() -> Self {
-        InEvent1 { val: 0 }
-    }
-    fn nullify(&mut self) -> Self {
-        let prev = *self;
-        *self = Self::null();
-        prev
-    }
-}
-impl Mergeable for InEvent1 {
-    fn merge(&self, rhs: Self) -> Self {
-        let ret = self.val | rhs.val;
-        InEvent1 { val: ret }
-    }
-}
-impl Subsumed for InEvent1 {
-    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
-        true
-    }
-}
-impl GraphEvent for InEvent1 {}
 
-#[derive(
-    PartialEq, Eq, std::hash::Hash, std::cmp::PartialOrd, std::cmp::Ord, Clone, Copy, Debug,
-)]
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
 pub struct InNode2 {
     idx: usize,
 }
-impl GraphNode for InNode2 {}
-impl From<InNode2> for usize {
-    fn from(idx: InNode2) -> Self {
-        idx.idx
-    }
-}
 
-impl From<usize> for InNode2 {
-    fn from(idx: usize) -> Self {
-        InNode2{idx}
-    }
-}
-
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, GraphEvent)]
 pub struct InEvent2 {
     val: i32,
 }
-impl Nullable for InEvent2 {
-    fn is_null(&self) -> bool {
-        self.val == 0
-    }
-    fn null() -> Self {
-        InEvent2 { val: 0 }
-    }
-    fn nullify(&mut self) -> Self {
-        let prev = *self;
-        *self = Self::null();
-        prev
-    }
-}
-impl Mergeable for InEvent2 {
-    fn merge(&self, rhs: Self) -> Self {
-        let ret = self.val | rhs.val;
-        InEvent2 { val: ret }
-    }
-}
-impl Subsumed for InEvent2 {
-    fn is_subsumed_under(&self, _rhs: &Self) -> bool {
-        true
-    }
-}
-impl GraphEvent for InEvent2 {}
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct TupleNode(usize);
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, GraphEvent)]
+pub struct Flags(u8);
 
 #[crusp_lazy_graph]
 struct GraphName {
@@ -190,7 +74,24 @@ impl VisitMut<InNode2> for MyVisitor {
     }
 }
 
+fn check_derives() {
+    assert_eq!(usize::from(TupleNode(3)), 3);
+    assert_eq!(TupleNode::from(4), TupleNode(4));
+    assert_eq!(OutNode::from(2).idx, 2);
+    assert!(Flags::null().is_null());
+    assert!(!Flags(1).is_null());
+    assert_eq!(Flags(1).merge(Flags(4)), Flags(5));
+    let mut flags = Flags(6);
+    assert_eq!(flags.nullify(), Flags(6));
+    assert!(flags.is_null());
+    assert!(Flags(7).is_subsumed_under(&Flags(3)));
+    assert!(Flags(7).is_subsumed_under(&Flags::null()));
+    assert!(!Flags(3).is_subsumed_under(&Flags(7)));
+    assert!(!Flags(1).is_subsumed_under(&Flags(2)));
+}
+
 pub fn main() {
+    check_derives();
     let oe0 = OutEvent { val: 0 };
     let oe1 = OutEvent { val: 1 };
     let oe2 = OutEvent { val: 2 };
@@ -247,4 +148,13 @@ pub fn main() {
     assert_eq!(event, Some((on0, oe0)));
     let event = graph.collect_and_pop(None);
     assert_eq!(event, None);
+
+    // the inputs of a single output link to it directly
+    graph.notify(&in12, &ie12);
+    graph.notify(&in2, &ie2);
+    let (outs, ins1, ins2) = graph.split_in_out();
+    ins1.trigger_events(|out| outs.collect_out_event(out, None));
+    ins2.trigger_events(|out| outs.collect_out_event(out, None));
+    assert_eq!(outs.pop(), Some((on1, oe1)));
+    assert_eq!(outs.pop(), Some((on2, oe2)));
 }
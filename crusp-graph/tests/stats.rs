@@ -0,0 +1,85 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Advisor(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[output]
+    advisors: (Advisor, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+    #[input]
+    bools: (BoolVar, FdEvent),
+}
+
+pub fn main() {
+    let degrees = Distribution::from_counts(vec![0, 1, 2, 3, 4, 9]);
+    assert_eq!(degrees.nodes, 6);
+    assert_eq!(degrees.total, 19);
+    assert_eq!((degrees.min, degrees.max), (0, 9));
+    assert!((degrees.mean - 19.0 / 6.0).abs() < 1e-9);
+    assert_eq!(degrees.histogram, vec![1, 1, 2, 1, 1]);
+    let empty = Distribution::from_counts(Vec::new());
+    assert_eq!((empty.nodes, empty.min, empty.max, empty.mean), (0, 0, 0, 0.0));
+
+    let mut graph = GraphName::builder();
+    for var in 0..3 {
+        graph.add_event(&Prop(0), &FdEvent::FIX, &Var(var), &FdEvent::BOUNDS, 0i64);
+    }
+    graph.add_event(&Prop(0), &FdEvent::FIX, &Var(0), &FdEvent::FIX, 0i64);
+    graph.add_event(&Prop(0), &FdEvent::FIX, &BoolVar(0), &FdEvent::FIX, 0i64);
+    graph.add_event(&Prop(2), &FdEvent::FIX, &Var(0), &FdEvent::BOUNDS, 0i64);
+    graph.add_event(&Advisor(0), &FdEvent::DOMAIN, &Var(2), &FdEvent::DOMAIN, 0i64);
+    let graph = graph.finalize();
+    let stats = graph.stats();
+
+    assert_eq!(stats.outputs.len(), 2);
+    let (name, props) = &stats.outputs[0];
+    assert_eq!(*name, "props");
+    // prop 0 has 3 variables and a boolean, prop 1 none and prop 2 one variable
+    assert_eq!((props.nodes, props.total, props.min, props.max), (3, 5, 0, 4));
+    let (name, advisors) = &stats.outputs[1];
+    assert_eq!(*name, "advisors");
+    assert_eq!((advisors.nodes, advisors.total), (1, 1));
+
+    let (name, vars) = &stats.inputs[0];
+    assert_eq!(*name, "vars");
+    // var 0 feeds prop 0 twice and prop 2, var 1 prop 0 and var 2 prop 0 and advisor 0
+    assert_eq!((vars.degrees.nodes, vars.degrees.total, vars.degrees.max), (3, 6, 3));
+    assert_eq!((vars.event_masks.min, vars.event_masks.max), (1, 2));
+    let (name, bools) = &stats.inputs[1];
+    assert_eq!(*name, "bools");
+    assert_eq!((bools.degrees.nodes, bools.degrees.total), (1, 1));
+
+    let report = stats.to_string();
+    assert!(report.contains("output props: 3 nodes, 5 in total"));
+    assert!(report.contains("input vars: links: 3 nodes, 6 in total"));
+
+    let mut builder = AdjacentListGraph::<Prop, Var>::builder();
+    builder.add_node(&Prop(1), &Var(0));
+    builder.add_node(&Prop(1), &Var(1));
+    let rev = builder.finalize();
+    assert_eq!(rev.sources(), 2);
+    assert_eq!(rev.stats().histogram, vec![1, 0, 1]);
+
+    let mut builder = LazyInputEventHandler::builder();
+    builder.add_event(Var(1), FdEvent::LB, OutCostEventLink::new(Prop(0), FdEvent::FIX, 0));
+    builder.add_event(Var(1), FdEvent::UB, OutCostEventLink::new(Prop(0), FdEvent::FIX, 0));
+    let handler = LazyInputEventHandler::new(builder.finalize());
+    let stats = handler.stats();
+    assert_eq!(stats.degrees.nodes, 2);
+    assert_eq!(stats.event_masks.histogram, vec![1, 0, 1]);
+}
@@ -0,0 +1,70 @@
+use crusp_graph::*;
+use crusp_graph_derive::{crusp_lazy_graph, GraphNode};
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Advisor(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    props: (Prop, FdEvent),
+    #[output]
+    advisors: (Advisor, FdEvent),
+    #[input]
+    vars: (Var, FdEvent),
+}
+
+fn props(graph: &GraphName, var: usize, event: Option<FdEvent>) -> Vec<(usize, FdEvent, i64)> {
+    let subscribers: SubscribersIter<'_, FdEvent, GraphNameLink, Prop, FdEvent> = match event {
+        Some(event) => graph.triggered_subscribers(&Var(var), &event),
+        None => graph.subscribers(&Var(var)),
+    };
+    subscribers.map(|(prop, mask, _, cost)| (prop.0, mask, cost)).collect()
+}
+
+pub fn main() {
+    let mut graph = GraphName::builder();
+    graph.add_event(&Prop(0), &FdEvent::FIX, &Var(0), &FdEvent::BOUNDS, 1i64);
+    graph.add_event(&Prop(1), &FdEvent::FIX, &Var(0), &FdEvent::DOMAIN, 2i64);
+    graph.add_event(&Prop(1), &FdEvent::FIX, &Var(1), &FdEvent::FIX, 2i64);
+    graph.add_event(&Advisor(0), &FdEvent::DOMAIN, &Var(0), &FdEvent::DOMAIN, 0i64);
+    let mut graph = graph.finalize();
+
+    // the subscribers of each output kind, in insertion order
+    assert_eq!(
+        props(&graph, 0, None),
+        vec![(0, FdEvent::BOUNDS, 1), (1, FdEvent::DOMAIN, 2)]
+    );
+    assert_eq!(props(&graph, 1, None), vec![(1, FdEvent::FIX, 2)]);
+    assert_eq!(props(&graph, 2, None), vec![]);
+    let advisors: Vec<_> = Subscribers::<Var, FdEvent, Advisor, FdEvent>::subscribers(&graph, &Var(0)).collect();
+    assert_eq!(advisors, vec![(Advisor(0), FdEvent::DOMAIN, FdEvent::DOMAIN, 0)]);
+
+    // degree of a variable over all the output kinds
+    let degree = Subscribers::<Var, FdEvent, Prop, FdEvent>::subscribers(&graph, &Var(0)).count()
+        + Subscribers::<Var, FdEvent, Advisor, FdEvent>::subscribers(&graph, &Var(0)).count();
+    assert_eq!(degree, 3);
+
+    // only the subscribers triggered by the event
+    assert_eq!(props(&graph, 0, Some(FdEvent::DOMAIN)), vec![(1, FdEvent::DOMAIN, 2)]);
+    assert_eq!(props(&graph, 0, Some(FdEvent::LB)).len(), 2);
+    assert_eq!(props(&graph, 1, Some(FdEvent::UB)), vec![]);
+
+    // links inserted in the finalized graph are listed
+    graph.add_event(&Prop(2), &FdEvent::FIX, &Var(1), &FdEvent::UB, 3i64);
+    assert_eq!(props(&graph, 1, Some(FdEvent::UB)), vec![(2, FdEvent::UB, 3)]);
+
+    let mut builder = LazyInputEventHandler::builder();
+    builder.add_event(Var(0), FdEvent::LB, OutCostEventLink::new(Prop(3), FdEvent::FIX, 4));
+    let handler = LazyInputEventHandler::new(builder.finalize());
+    let subscribers: Vec<_> = handler.subscribers(&Var(0)).collect();
+    assert_eq!(subscribers, vec![(Prop(3), FdEvent::LB, FdEvent::FIX, 4)]);
+    assert_eq!(handler.triggered_subscribers(&Var(0), &FdEvent::UB).count(), 0);
+    assert_eq!(handler.links(&Var(0)).count(), 1);
+}
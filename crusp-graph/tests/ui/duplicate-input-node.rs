@@ -0,0 +1,13 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    out: (OutNode, OutEvent),
+    #[input]
+    in1: (InNode, InEvent),
+    #[input]
+    in2: (InNode, InEvent),
+}
+
+fn main() {}
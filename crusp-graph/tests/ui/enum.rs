@@ -0,0 +1,8 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+enum GraphName {
+    Out,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use crusp_graph_derive::GraphEvent;
+
+#[derive(Clone, Copy, Debug, GraphEvent)]
+struct Event(f32);
+
+fn main() {}
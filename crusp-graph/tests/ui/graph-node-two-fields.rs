@@ -0,0 +1,9 @@
+use crusp_graph_derive::GraphNode;
+
+#[derive(GraphNode)]
+struct Node {
+    idx: usize,
+    level: usize,
+}
+
+fn main() {}
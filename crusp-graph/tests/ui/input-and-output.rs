@@ -0,0 +1,12 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    #[input]
+    out: (OutNode, OutEvent),
+    #[input]
+    in1: (InNode, InEvent),
+}
+
+fn main() {}
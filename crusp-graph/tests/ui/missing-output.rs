@@ -0,0 +1,9 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[input]
+    in1: (InNode, InEvent),
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    out: OutNode,
+    #[input]
+    in1: (InNode, InEvent),
+}
+
+fn main() {}
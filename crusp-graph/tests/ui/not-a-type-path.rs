@@ -0,0 +1,11 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    out: (OutNode, OutEvent),
+    #[input]
+    in1: (&'static InNode, InEvent),
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName((OutNode, OutEvent), (InNode, InEvent));
+
+fn main() {}
@@ -0,0 +1,10 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output]
+    out: (OutNode, OutEvent),
+    in1: (InNode, InEvent),
+}
+
+fn main() {}
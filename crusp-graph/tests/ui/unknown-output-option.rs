@@ -0,0 +1,11 @@
+use crusp_graph_derive::crusp_lazy_graph;
+
+#[crusp_lazy_graph]
+struct GraphName {
+    #[output(priority = 1)]
+    out: (OutNode, OutEvent),
+    #[input]
+    in1: (InNode, InEvent),
+}
+
+fn main() {}
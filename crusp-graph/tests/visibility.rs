@@ -0,0 +1,62 @@
+use crusp_graph::*;
+use crusp_graph_derive::GraphNode;
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Prop(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct Var(usize);
+
+#[derive(PartialEq, Eq, std::hash::Hash, PartialOrd, Ord, Clone, Copy, Debug, GraphNode)]
+pub struct BoolVar(usize);
+
+mod graphs {
+    use crusp_graph_derive::crusp_lazy_graph;
+    use super::{BoolVar, Prop, Var};
+    use crusp_graph::{FdEvent, FlagEvent};
+
+    /// Graph used from another module.
+    #[crusp_lazy_graph]
+    #[deny(dead_code)]
+    pub struct GraphName {
+        #[output]
+        props: (Prop, FdEvent),
+        #[input]
+        vars: (Var, FdEvent),
+        #[input]
+        bools: (BoolVar, FlagEvent<u8>),
+    }
+
+    #[crusp_lazy_graph]
+    /// Graph only used in this crate, documented after the attribute.
+    pub(crate) struct CrateGraph {
+        #[output]
+        props: (Prop, FdEvent),
+        #[input]
+        vars: (Var, FdEvent),
+    }
+}
+
+use graphs::{CrateGraph, CrateGraphBuilder, GraphName, GraphNameBuilder};
+
+pub fn main() {
+    let oe1 = FdEvent::BOUNDS;
+    let on0 = Prop(0);
+    let in1 = Var(0);
+    let ie1 = FdEvent::BOUNDS;
+    let in2 = BoolVar(0);
+    let ie2 = FlagEvent::new(0b01u8);
+
+    let mut builder = GraphNameBuilder::new();
+    builder.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    builder.add_event(&on0, &oe1, &in2, &ie2, 0i64);
+    let mut graph: GraphName = builder.finalize();
+    graph.notify(&in2, &ie2);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+
+    let mut builder: CrateGraphBuilder = CrateGraph::builder();
+    builder.add_event(&on0, &oe1, &in1, &ie1, 0i64);
+    let mut graph = builder.finalize();
+    graph.notify(&in1, &ie1);
+    assert_eq!(graph.collect_and_pop(None), Some((on0, oe1)));
+}